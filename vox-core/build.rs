@@ -1,25 +1,57 @@
 use anyhow::*;
+use std::fmt::Write;
 use std::path::*;
-use fs_extra::copy_items;
-use fs_extra::dir::CopyOptions;
 use std::env;
+use std::fs;
 
-fn get_output_path() -> PathBuf {
-    //<root or manifest path>/target/<profile>/
-    let manifest_dir_string = env::var("CARGO_MANIFEST_DIR").unwrap();
-    let build_type = env::var("PROFILE").unwrap();
-    let path = Path::new(&manifest_dir_string).join("target").join(build_type);
-    println!("cargo:rustc-env=VOX_OUTPUT_DIR={}", path.to_str().unwrap());
-    path
+// every file under dir, recursively
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_hidden = path.file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+        if is_hidden {
+            continue;
+        } else if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+// writes the table EmbeddedSource reads from
+fn write_embedded_assets(res_dir: &Path, out_path: &Path) -> Result<()> {
+    let mut files = Vec::new();
+    if env::var_os("CARGO_FEATURE_EMBED_ASSETS").is_some() {
+        collect_files(res_dir, &mut files)?;
+        files.sort();
+    }
+
+    let mut table = String::from("&[\n");
+    for file in files {
+        let name = file.strip_prefix(res_dir)?
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        writeln!(table, "    ({:?}, include_bytes!({:?}) as &[u8]),", name, file.to_string_lossy())?;
+    }
+    table.push(']');
+
+    fs::write(out_path, table)?;
+    Ok(())
 }
 
 fn main() -> Result<()> {
-    println!("cargo:rerun-if-changed=res/*");
+    println!("cargo:rerun-if-changed=res");
+
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
 
-    let mut copy_options = CopyOptions::new();
-    copy_options.overwrite = true;
-    let paths_to_copy = vec!["res/"];
-    copy_items(&paths_to_copy, get_output_path(), &copy_options)?;
+    write_embedded_assets(&manifest_dir.join("res"), &out_dir.join("embedded_assets.rs"))?;
 
     Ok(())
 }
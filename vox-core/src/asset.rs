@@ -1,5 +1,116 @@
+pub mod handle;
+pub mod source;
+pub mod archive;
+
+use std::{any::Any, error::Error, fmt::Display, path::Path, sync::Arc};
+
+use handle::Handle;
+
+use crate::resources::asset_server::AssetServer;
+
 pub trait Asset {
     // This is treated as an ID
     // Two resources CANNOT have the same file_name
     fn file_name(&self) -> &str;
 }
+
+// Loaders are registered in the AssetServer for every extension
+// they return, and only for the asset type they produce
+pub trait AssetLoader
+where Self: Send + Sync + 'static {
+    type Asset: Asset + Send + Sync + 'static;
+
+    fn extensions(&self) -> &[&str];
+    fn load(&self, bytes: &[u8], ctx: &mut LoadContext) -> Result<Self::Asset, AssetError>;
+}
+
+pub struct LoadContext<'a> {
+    pub file_name: &'a str,
+    pub asset_server: &'a mut AssetServer,
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+}
+
+impl LoadContext<'_> {
+    // the directory other files referenced by this asset are relative to
+    pub fn directory(&self) -> &Path {
+        Path::new(self.file_name)
+            .parent()
+            .unwrap_or(Path::new(""))
+    }
+
+    pub fn read(&self, file_name: &str) -> Result<Vec<u8>, AssetError> {
+        self.asset_server.read(file_name)
+    }
+
+    pub fn get_or_load<T>(&mut self, file_name: &str) -> Result<Handle<T>, AssetError>
+    where
+        T: Asset + Send + Sync + 'static,
+    {
+        self.asset_server.get_or_load(file_name, self.device, self.queue)
+    }
+
+    pub fn error(&self, source: impl Into<Box<dyn Error + Send + Sync>>) -> AssetError {
+        AssetError::Load {
+            file_name: self.file_name.to_string(),
+            source: source.into(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    NoLoader {
+        file_name: String,
+        type_name: &'static str,
+    },
+    Io {
+        file_name: String,
+        source: std::io::Error,
+    },
+    Load {
+        file_name: String,
+        source: Box<dyn Error + Send + Sync>,
+    },
+}
+
+impl Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetError::NoLoader { file_name, type_name } => {
+                write!(f, "No loader registered for {} as {}", file_name, type_name)
+            },
+            AssetError::Io { file_name, source } => {
+                write!(f, "Could not read {}: {}", file_name, source)
+            },
+            AssetError::Load { file_name, source } => {
+                write!(f, "Could not load {}: {}", file_name, source)
+            },
+        }
+    }
+}
+
+impl Error for AssetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AssetError::NoLoader { .. } => None,
+            AssetError::Io { source, .. } => Some(source),
+            AssetError::Load { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+
+pub(crate) trait ErasedAssetLoader
+where Self: Send + Sync + 'static {
+    fn load_erased(&self, bytes: &[u8], ctx: &mut LoadContext)
+        -> Result<Arc<dyn Any + Send + Sync>, AssetError>;
+}
+
+impl<L: AssetLoader> ErasedAssetLoader for L {
+    fn load_erased(&self, bytes: &[u8], ctx: &mut LoadContext)
+        -> Result<Arc<dyn Any + Send + Sync>, AssetError>
+    {
+        let asset = self.load(bytes, ctx)?;
+        Ok(Arc::new(asset))
+    }
+}
@@ -0,0 +1,49 @@
+use std::{io, path::Path};
+
+use vox_pack::Archive;
+
+use super::source::AssetSource;
+
+// Reads assets packed with `cargo xtask pack`, the index is
+// checked when opening and every entry when it is read
+pub struct ArchiveSource {
+    name: String,
+    archive: Archive,
+}
+
+impl ArchiveSource {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let archive = Archive::open(path)?;
+        let name = path.to_string_lossy()
+            .to_string();
+
+        Ok(Self {
+            name,
+            archive,
+        })
+    }
+
+    pub fn from_bytes(name: &str, data: Vec<u8>) -> io::Result<Self> {
+        let archive = Archive::from_bytes(data)?;
+        let name = name.to_string();
+
+        Ok(Self {
+            name,
+            archive,
+        })
+    }
+
+    pub fn archive(&self) -> &Archive {
+        &self.archive
+    }
+}
+
+impl AssetSource for ArchiveSource {
+    fn read(&self, file_name: &str) -> io::Result<Vec<u8>> {
+        self.archive.read(file_name)
+    }
+
+    fn describe(&self) -> String {
+        format!("archive {} ({} files)", self.name, self.archive.len())
+    }
+}
@@ -0,0 +1,97 @@
+use std::{fmt::Debug, ops::Deref, sync::{Arc, Weak}};
+
+// A strong handle keeps its asset loaded in the AssetServer,
+// once every strong handle is dropped the asset can be unloaded
+pub struct Handle<T> {
+    path: Arc<str>,
+    asset: Arc<T>,
+}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(path: Arc<str>, asset: Arc<T>) -> Self {
+        Self {
+            path,
+            asset,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn downgrade(&self) -> WeakHandle<T> {
+        WeakHandle {
+            path: self.path.clone(),
+            asset: Arc::downgrade(&self.asset),
+        }
+    }
+
+    pub fn ptr_eq(&self, other: &Handle<T>) -> bool {
+        Arc::ptr_eq(&self.asset, &other.asset)
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            asset: self.asset.clone(),
+        }
+    }
+}
+
+impl<T> Deref for Handle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.asset
+    }
+}
+
+impl<T: Debug> Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handle")
+            .field("path", &self.path)
+            .field("asset", &self.asset)
+            .finish()
+    }
+}
+
+// A weak handle does not keep its asset loaded
+pub struct WeakHandle<T> {
+    path: Arc<str>,
+    asset: Weak<T>,
+}
+
+impl<T> WeakHandle<T> {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn upgrade(&self) -> Option<Handle<T>> {
+        self.asset.upgrade()
+            .map(|asset| Handle::new(self.path.clone(), asset))
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.asset.strong_count() > 0
+    }
+}
+
+impl<T> Clone for WeakHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            asset: self.asset.clone(),
+        }
+    }
+}
+
+impl<T> Debug for WeakHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WeakHandle")
+            .field("path", &self.path)
+            .field("loaded", &self.is_loaded())
+            .finish()
+    }
+}
@@ -0,0 +1,128 @@
+use std::{fmt::Display, io, path::{Path, PathBuf}};
+
+use log::info;
+
+// generated by build.rs, empty without the embed-assets feature
+static EMBEDDED_ASSETS: &[(&str, &[u8])] = include!(concat!(env!("OUT_DIR"), "/embedded_assets.rs"));
+
+// set to a folder or an archive to load assets from there
+pub const ASSETS_ENV_VAR: &str = "VOX_ASSETS";
+pub const ARCHIVE_FILE_NAME: &str = "res.pack";
+
+// Where the AssetServer reads files from
+// file names are normalized and relative to the res folder
+pub trait AssetSource
+where Self: Send + Sync + 'static {
+    fn read(&self, file_name: &str) -> io::Result<Vec<u8>>;
+    fn describe(&self) -> String;
+}
+
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+
+        Self {
+            root,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl AssetSource for DirectorySource {
+    fn read(&self, file_name: &str) -> io::Result<Vec<u8>> {
+        std::fs::read(self.root.join(file_name))
+    }
+
+    fn describe(&self) -> String {
+        format!("directory {}", self.root.display())
+    }
+}
+
+#[derive(Default)]
+pub struct EmbeddedSource;
+
+impl AssetSource for EmbeddedSource {
+    fn read(&self, file_name: &str) -> io::Result<Vec<u8>> {
+        EMBEDDED_ASSETS.iter()
+            .find(|(name, _)| *name == file_name)
+            .map(|(_, bytes)| bytes.to_vec())
+            .ok_or_else(|| not_found(file_name))
+    }
+
+    fn describe(&self) -> String {
+        format!("{} embedded assets", EMBEDDED_ASSETS.len())
+    }
+}
+
+pub(crate) fn not_found(file_name: impl Display) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} not found", file_name))
+}
+
+// picks the first of:
+// VOX_ASSETS, res.pack next to the executable, res/ next to the executable,
+// the crate's res/ folder in debug builds, the embedded assets
+pub fn default_source() -> Box<dyn AssetSource> {
+    let source = find_source();
+    info!("Loading assets from {}", source.describe());
+
+    source
+}
+
+#[cfg(not(target_arch="wasm32"))]
+fn find_source() -> Box<dyn AssetSource> {
+    if let Some(path) = std::env::var_os(ASSETS_ENV_VAR) {
+        match open_path(Path::new(&path)) {
+            Ok(source) => return source,
+            Err(e) => log::error!("Could not open {} from {}: {}", path.to_string_lossy(), ASSETS_ENV_VAR, e),
+        }
+    }
+
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+
+    if let Some(exe_dir) = exe_dir {
+        for path in [exe_dir.join(ARCHIVE_FILE_NAME), exe_dir.join("res")] {
+            if !path.exists() {
+                continue;
+            }
+
+            match open_path(&path) {
+                Ok(source) => return source,
+                Err(e) => log::error!("Could not open {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    // lets hot reloading and editing res/ work without rebuilding
+    #[cfg(debug_assertions)]
+    {
+        let res_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("res");
+        if res_dir.is_dir() {
+            return Box::new(DirectorySource::new(res_dir));
+        }
+    }
+
+    Box::new(EmbeddedSource)
+}
+
+#[cfg(target_arch="wasm32")]
+fn find_source() -> Box<dyn AssetSource> {
+    Box::new(EmbeddedSource)
+}
+
+#[cfg(not(target_arch="wasm32"))]
+fn open_path(path: &Path) -> io::Result<Box<dyn AssetSource>> {
+    if path.is_dir() {
+        Ok(Box::new(DirectorySource::new(path)))
+    } else {
+        Ok(Box::new(super::archive::ArchiveSource::open(path)?))
+    }
+}
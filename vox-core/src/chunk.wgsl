@@ -1,70 +0,0 @@
-const TEX_COORDS = array(
-    vec2(0.0, 0.0),
-    vec2(1.0, 0.0),
-    vec2(1.0, 1.0),
-    vec2(0.0, 1.0),
-);
-
-struct CameraUniform {
-    view_proj: mat4x4<f32>,
-}
-
-struct VertexInput {
-    @location(0) position: vec3<f32>,
-}
-
-struct FaceInfo {
-    // O orientation
-    // H height
-    // W width
-    // 0bOOOHHHHHHWWWWWWZZZZZZYYYYYYXXXXXX
-    @location(3) packed_info: u32,
-}
-
-struct InstanceInput {
-    @location(5) model_mat_0: vec4<f32>,
-    @location(6) model_mat_1: vec4<f32>,
-    @location(7) model_mat_2: vec4<f32>,
-    @location(8) model_mat_3: vec4<f32>,
-}
-
-struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
-    @location(0) tex_coords: vec2<f32>,
-}
-
-@group(1) @binding(0)
-var<uniform> camera: CameraUniform;
-
-@vertex
-fn vs_main(
-    model: VertexInput, face_info: FaceInfo, instance: InstanceInput
-) -> VertexOutput {
-    let model_matrix = mat4x4<f32>(
-        instance.model_mat_0,
-        instance.model_mat_1,
-        instance.model_mat_2,
-        instance.model_mat_3,
-    );
-
-    let packed_info = face_info.packed_info;
-    let width = packed_info & 63;
-    let height = (packed_info >> 6) & 63;
-    let orientation_idx = (packed_info >> 8) & 7;
-
-    var out: VertexOutput;
-    out.tex_coords = model.tex_coords;
-    out.clip_position = camera.view_proj * model_matrix * vec4<f32>(model.position, 1.0);
-    return out;
-}
-
-@group(0) @binding(0)
-var t_diffuse: texture_2d<f32>;
-
-@group(0) @binding(1)
-var s_diffuse: sampler;
-
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
-}
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context};
+
+pub const USAGE: &str = "\
+Usage: vox-core [OPTIONS]
+
+Options:
+    --flythrough         fly the camera along a path and report frame times
+    --headless           run without a window, rendering into a texture
+    --no-render          with --headless, only run the updates, no adapter needed
+    --frames <N>         timed frames of the flythrough, or frames to run
+                         headless [default: 1000]
+    --seed <N>           picks the flythrough's camera path [default: 0]
+    --output <PATH>      where the flythrough report goes [default: flythrough.json]
+    --screenshot <PATH>  save the last headless frame as an image
+    --software           use the fallback (software) adapter
+    --help               print this message";
+
+// What the engine was launched with, see USAGE
+#[derive(Debug, Clone)]
+pub struct LaunchArgs {
+    pub flythrough: Option<FlythroughArgs>,
+    pub headless: bool,
+    pub no_render: bool,
+    pub frames: u32,
+    pub screenshot: Option<PathBuf>,
+    pub software_adapter: bool,
+    pub show_help: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct FlythroughArgs {
+    pub seed: u64,
+    pub frames: u32,
+    pub output: PathBuf,
+}
+
+impl Default for FlythroughArgs {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            frames: 1000,
+            output: PathBuf::from("flythrough.json"),
+        }
+    }
+}
+
+impl Default for LaunchArgs {
+    fn default() -> Self {
+        Self {
+            flythrough: None,
+            headless: false,
+            no_render: false,
+            frames: 1000,
+            screenshot: None,
+            software_adapter: false,
+            show_help: false,
+        }
+    }
+}
+
+impl LaunchArgs {
+    // expects the arguments without the executable
+    pub fn parse(args: impl IntoIterator<Item = String>) -> anyhow::Result<Self> {
+        let mut launch_args = LaunchArgs::default();
+        let mut is_flythrough = false;
+        let mut flythrough_args = FlythroughArgs::default();
+        // only a flythrough has a seed or writes a report
+        let mut flythrough_only_arg = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next()
+                .with_context(|| format!("{} expects a value", arg));
+
+            match arg.as_str() {
+                "--flythrough" => is_flythrough = true,
+                "--headless" => launch_args.headless = true,
+                "--no-render" => launch_args.no_render = true,
+                "--frames" => {
+                    launch_args.frames = value()?.parse()?;
+                    flythrough_args.frames = launch_args.frames;
+                },
+                "--seed" => {
+                    flythrough_args.seed = value()?.parse()?;
+                    flythrough_only_arg = Some(arg);
+                },
+                "--output" => {
+                    flythrough_args.output = PathBuf::from(value()?);
+                    flythrough_only_arg = Some(arg);
+                },
+                "--screenshot" => launch_args.screenshot = Some(PathBuf::from(value()?)),
+                "--software" => launch_args.software_adapter = true,
+                "--help" | "-h" => launch_args.show_help = true,
+                _ => return Err(anyhow!("Unknown argument {}", arg)),
+            }
+        }
+
+        if is_flythrough {
+            launch_args.flythrough = Some(flythrough_args);
+        } else if let Some(arg) = flythrough_only_arg {
+            return Err(anyhow!("{} only works with --flythrough", arg));
+        }
+
+        if launch_args.no_render && !launch_args.headless {
+            return Err(anyhow!("--no-render only works with --headless"));
+        }
+
+        if launch_args.no_render && launch_args.screenshot.is_some() {
+            return Err(anyhow!("--screenshot needs a renderer, it does not work with --no-render"));
+        }
+
+        Ok(launch_args)
+    }
+}
@@ -0,0 +1,31 @@
+use bevy_ecs::{entity::Entity, system::EntityCommands, world::World};
+
+use crate::components::hierarchy;
+
+pub trait VoxEntityCommandsExt {
+    fn set_parent(&mut self, parent: Entity) -> &mut Self;
+    fn remove_parent(&mut self) -> &mut Self;
+    fn despawn_recursive(&mut self);
+}
+
+impl VoxEntityCommandsExt for EntityCommands<'_> {
+    fn set_parent(&mut self, parent: Entity) -> &mut Self {
+        self.add(move |child: Entity, world: &mut World| {
+            if let Err(e) = hierarchy::set_parent(world, child, parent) {
+                log::error!("{}", e);
+            }
+        })
+    }
+
+    fn remove_parent(&mut self) -> &mut Self {
+        self.add(|child: Entity, world: &mut World| {
+            hierarchy::remove_parent(world, child);
+        })
+    }
+
+    fn despawn_recursive(&mut self) {
+        self.add(|entity: Entity, world: &mut World| {
+            hierarchy::despawn_recursive(world, entity);
+        });
+    }
+}
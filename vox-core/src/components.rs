@@ -1,2 +1,5 @@
 pub mod camerable;
 pub mod speed;
+pub mod transform;
+pub mod hierarchy;
+pub mod mesh_instance;
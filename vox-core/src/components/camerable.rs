@@ -1,9 +1,40 @@
 use bevy_ecs::prelude::*;
+use bytemuck::{Pod, Zeroable};
 use cgmath::{Matrix4, Point3, SquareMatrix, Vector3};
 
-pub type CameraUniform = [[f32;4];4];
+// cgmath projections map depth to -1..1, wgpu expects 0..1
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.5,
+    0.0, 0.0, 0.0, 1.0,
+);
 
-#[derive(Component)]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct CameraUniform {
+    pub view_proj: [[f32; 4]; 4],
+    // w is padding, lighting needs the position for specular
+    pub view_position: [f32; 4],
+}
+
+impl CameraUniform {
+    pub fn new(view_proj: Matrix4<f32>, view_position: Point3<f32>) -> Self {
+        Self {
+            view_proj: view_proj.into(),
+            view_position: [view_position.x, view_position.y, view_position.z, 1.0],
+        }
+    }
+}
+
+impl From<Matrix4<f32>> for CameraUniform {
+    fn from(view_proj: Matrix4<f32>) -> Self {
+        Self::new(view_proj, Point3::new(0.0, 0.0, 0.0))
+    }
+}
+
+#[derive(Component, Clone)]
 pub struct CameraComponent {
     pub target: Point3<f32>,
     pub aspect: f32,
@@ -16,6 +47,10 @@ pub struct CameraComponent {
     pub pitch: f32,
     pub view_proj: CameraUniform,
     pub position: Point3<f32>,
+    // where the last fixed update left the camera
+    pub previous_position: Point3<f32>,
+    pub previous_target: Point3<f32>,
+    // in units per second
     pub speed: f32,
 }
 
@@ -23,8 +58,10 @@ impl CameraComponent {
     pub fn debug(config: &wgpu::SurfaceConfiguration) -> Self {
         Self {
             position: (0.1, 0.2, 0.3).into(),
-            speed: 0.3,
+            previous_position: (0.1, 0.2, 0.3).into(),
+            speed: 20.0,
             target: (0.0, 0.0, 0.0).into(),
+            previous_target: (0.0, 0.0, 0.0).into(),
             up: cgmath::Vector3::unit_y(),
             aspect: config.width as f32 / config.height as f32,
             fovy: 45.0,
@@ -36,4 +73,25 @@ impl CameraComponent {
             view_proj: Matrix4::identity().into(),
         }
     }
+
+    // call before moving the camera in a fixed update
+    pub fn store_previous(&mut self) {
+        self.previous_position = self.position;
+        self.previous_target = self.target;
+    }
+
+    // moves without blending from the old position
+    pub fn snap_to(&mut self, position: Point3<f32>, target: Point3<f32>) {
+        self.position = position;
+        self.target = target;
+        self.store_previous();
+    }
+
+    // the camera between the last two fixed updates, see Time::alpha
+    pub fn interpolated(&self, alpha: f32) -> Self {
+        let mut camera = self.clone();
+        camera.position = self.previous_position + (self.position - self.previous_position) * alpha;
+        camera.target = self.previous_target + (self.target - self.previous_target) * alpha;
+        camera
+    }
 }
@@ -0,0 +1,157 @@
+use std::{error::Error, fmt::Display};
+
+use bevy_ecs::prelude::*;
+use cgmath::Matrix4;
+
+use super::transform::{GlobalTransformComponent, TransformComponent};
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParentComponent(pub Entity);
+
+#[derive(Component, Debug, Default, Clone)]
+pub struct ChildrenComponent(pub Vec<Entity>);
+
+#[derive(Debug)]
+pub enum HierarchyError {
+    MissingEntity(Entity),
+    // the parent is the child itself or one of its descendants
+    Cycle {
+        child: Entity,
+        parent: Entity,
+    },
+}
+
+impl Display for HierarchyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HierarchyError::MissingEntity(entity) => {
+                write!(f, "Entity {} does not exist", entity)
+            },
+            HierarchyError::Cycle { child, parent } => {
+                write!(f, "Parenting {} to {} would make it its own ancestor", child, parent)
+            },
+        }
+    }
+}
+
+impl Error for HierarchyError {}
+
+pub fn set_parent(world: &mut World, child: Entity, parent: Entity) -> Result<(), HierarchyError> {
+    for entity in [child, parent] {
+        if world.get_entity(entity).is_none() {
+            return Err(HierarchyError::MissingEntity(entity));
+        }
+    }
+
+    if is_ancestor_or_self(world, child, parent) {
+        return Err(HierarchyError::Cycle { child, parent });
+    }
+
+    remove_parent(world, child);
+
+    world.entity_mut(child)
+        .insert(ParentComponent(parent));
+
+    let mut parent_mut = world.entity_mut(parent);
+    match parent_mut.get_mut::<ChildrenComponent>() {
+        Some(mut children) => children.0.push(child),
+        None => {
+            parent_mut.insert(ChildrenComponent(vec![child]));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn remove_parent(world: &mut World, child: Entity) {
+    let Some(mut child_mut) = world.get_entity_mut(child) else {
+        return;
+    };
+
+    if let Some(ParentComponent(old_parent)) = child_mut.take::<ParentComponent>() {
+        if let Some(mut children) = world.get_mut::<ChildrenComponent>(old_parent) {
+            children.0.retain(|entity| *entity != child);
+        }
+    }
+}
+
+// walks up from entity, the hierarchy itself never has cycles
+fn is_ancestor_or_self(world: &World, ancestor: Entity, entity: Entity) -> bool {
+    let mut current = Some(entity);
+    while let Some(entity) = current {
+        if entity == ancestor {
+            return true;
+        }
+
+        current = world.get::<ParentComponent>(entity)
+            .map(|parent| parent.0);
+    }
+
+    false
+}
+
+// detaches entity from its parent and despawns it with all its descendants,
+// despawning only the parent would leave the children pointing at nothing
+pub fn despawn_recursive(world: &mut World, entity: Entity) {
+    remove_parent(world, entity);
+
+    let mut stack = vec![entity];
+    while let Some(entity) = stack.pop() {
+        let Some(entity_mut) = world.get_entity_mut(entity) else {
+            continue;
+        };
+
+        if let Some(children) = entity_mut.get::<ChildrenComponent>() {
+            stack.extend(children.0.iter().copied());
+        }
+
+        entity_mut.despawn();
+    }
+}
+
+// runs from the roots down, so a child always sees
+// the global transform its parent has this frame.
+// an entity whose parent is gone or has no transform counts as a root
+pub fn propagate_transforms(
+    nodes: Query<(Entity, &TransformComponent, Option<&ParentComponent>, Option<&ChildrenComponent>)>,
+    mut globals: Query<&mut GlobalTransformComponent>,
+) {
+    for (entity, transform, parent_opt, children_opt) in &nodes {
+        let is_root = !parent_opt.is_some_and(|parent| nodes.contains(parent.0));
+        if !is_root {
+            continue;
+        }
+
+        let matrix = transform.to_matrix();
+        if let Ok(mut global) = globals.get_mut(entity) {
+            global.0 = matrix;
+        }
+
+        // the subtree is updated even when the root has no global transform
+        if let Some(children) = children_opt {
+            propagate_children(matrix, children, &nodes, &mut globals);
+        }
+    }
+}
+
+fn propagate_children(
+    parent_matrix: Matrix4<f32>,
+    children: &ChildrenComponent,
+    nodes: &Query<(Entity, &TransformComponent, Option<&ParentComponent>, Option<&ChildrenComponent>)>,
+    globals: &mut Query<&mut GlobalTransformComponent>,
+) {
+    for child in children.0.iter() {
+        let Ok((_, transform, _, grandchildren_opt)) = nodes.get(*child) else {
+            continue;
+        };
+
+        let matrix = parent_matrix * transform.to_matrix();
+        if let Ok(mut global) = globals.get_mut(*child) {
+            global.0 = matrix;
+        }
+
+        if let Some(grandchildren) = grandchildren_opt {
+            propagate_children(matrix, grandchildren, nodes, globals);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy_ecs::prelude::*;
+use cgmath::Matrix4;
+
+use crate::{resources::{render_context::RenderContext, render_server::{MeshId, ModelId, RenderServer}}, InstanceRaw};
+
+use super::transform::GlobalTransformComponent;
+
+// draws the mesh with this id at the entity's global transform
+// every entity pointing to the same mesh becomes one instance
+#[derive(Component, Debug, Clone, Copy)]
+pub struct MeshInstanceComponent {
+    pub mesh_id: MeshId,
+}
+
+// same as MeshInstanceComponent but for every mesh of a model
+// pushed with RenderServer::push_model
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ModelInstanceComponent {
+    pub model_id: ModelId,
+}
+
+pub fn sync_mesh_instances(query: Query<(&GlobalTransformComponent, &MeshInstanceComponent)>,
+    model_query: Query<(&GlobalTransformComponent, &ModelInstanceComponent)>,
+    mut render_server: ResMut<RenderServer>,
+    render_ctx: Res<RenderContext>,
+    mut last_synced: Local<HashSet<MeshId>>,
+) {
+    let mut instances: HashMap<MeshId, Vec<InstanceRaw>> = HashMap::new();
+    for (global, mesh_instance) in &query {
+        instances.entry(mesh_instance.mesh_id)
+            .or_default()
+            .push(InstanceRaw::from(global.matrix()));
+    }
+
+    let mut model_meshes: HashMap<ModelId, Vec<(MeshId, Matrix4<f32>)>> = HashMap::new();
+    for (global, model_instance) in &model_query {
+        let meshes = model_meshes.entry(model_instance.model_id)
+            .or_insert_with(|| render_server.model_meshes(model_instance.model_id));
+
+        for (mesh_id, transform) in meshes.iter() {
+            instances.entry(*mesh_id)
+                .or_default()
+                .push(InstanceRaw::from(global.matrix() * transform));
+        }
+    }
+
+    // meshes that lost all of their entities should stop drawing
+    for mesh_id in last_synced.drain() {
+        instances.entry(mesh_id)
+            .or_default();
+    }
+
+    for (mesh_id, raw_instances) in instances.iter() {
+        render_server.write_mesh_instances(*mesh_id,
+            raw_instances,
+            &render_ctx.device,
+            &render_ctx.queue
+        );
+
+        if !raw_instances.is_empty() {
+            last_synced.insert(*mesh_id);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use bevy_ecs::prelude::*;
+use cgmath::{Matrix4, One, Quaternion, SquareMatrix, Vector3};
+
+// the transform of an entity relative to its parent
+// or to the world if it has no parent
+#[derive(Component, Debug, Clone, Copy)]
+pub struct TransformComponent {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Default for TransformComponent {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::new(0.0, 0.0, 0.0),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl TransformComponent {
+    pub fn from_translation(translation: Vector3<f32>) -> Self {
+        Self {
+            translation,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_rotation(rotation: Quaternion<f32>) -> Self {
+        Self {
+            rotation,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_scale(mut self, scale: Vector3<f32>) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn to_matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+// computed by propagate_transforms, do not write this directly
+#[derive(Component, Debug, Clone, Copy)]
+pub struct GlobalTransformComponent(pub Matrix4<f32>);
+
+impl Default for GlobalTransformComponent {
+    fn default() -> Self {
+        Self(Matrix4::identity())
+    }
+}
+
+impl GlobalTransformComponent {
+    pub fn matrix(&self) -> Matrix4<f32> {
+        self.0
+    }
+
+    pub fn translation(&self) -> Vector3<f32> {
+        self.0.w.truncate()
+    }
+}
+
+#[derive(Bundle, Default)]
+pub struct TransformBundle {
+    pub local: TransformComponent,
+    pub global: GlobalTransformComponent,
+}
+
+impl From<TransformComponent> for TransformBundle {
+    fn from(local: TransformComponent) -> Self {
+        Self {
+            local,
+            global: GlobalTransformComponent(local.to_matrix()),
+        }
+    }
+}
@@ -1,11 +1,12 @@
 use wgpu::{util::{DeviceExt, DrawIndexedIndirectArgs}, Buffer, Device};
 
-use crate::{render::vertex::{Index, Vertex}, InstanceData};
+use crate::{render::vertex::{Index, Vertex}, InstanceData, InstanceRaw};
 
 pub trait VoxDeviceExt {
     fn compute_vertex_buffer(&self, vertices: &[Vertex]) -> Buffer;
     fn compute_index_buffer(&self, indices: &[Index]) -> Buffer;
     fn compute_instance_buffer(&self, instances: &[InstanceData]) -> Buffer;
+    fn compute_raw_instance_buffer(&self, instances_raw: &[InstanceRaw]) -> Buffer;
     fn compute_indirect_indexed_buffer(&self,
         indirect_args: &[DrawIndexedIndirectArgs]
     ) -> Buffer;
@@ -48,10 +49,15 @@ impl VoxDeviceExt for Device {
             .map(InstanceData::to_raw)
             .collect::<Vec<_>>();
 
+        self.compute_raw_instance_buffer(&instances_raw)
+    }
+
+    fn compute_raw_instance_buffer(&self, instances_raw: &[InstanceRaw]) -> Buffer {
         self.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Mesh Instance Buffer"),
-            usage: wgpu::BufferUsages::VERTEX,
-            contents: bytemuck::cast_slice(&instances_raw),
+            // instances can be rewritten by sync_mesh_instances
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            contents: bytemuck::cast_slice(instances_raw),
         })
     }
 }
@@ -0,0 +1,253 @@
+use anyhow::{anyhow, Context};
+use bevy_ecs::world::World;
+
+use crate::{cli::LaunchArgs, insert_core_resources, insert_render_resources, request_device, REQUIRED_FEATURES, resources::{flythrough::Flythrough, game_state::GameState, render_context::{RenderContext, RenderTarget}}, screens::{game::GameScreen, menu::MenuScreen, screen::Screen}, start_flythrough, AppState};
+
+// Runs the game loop without a window, on a clock the caller advances.
+// Without a renderer only the update schedules run, so their systems
+// must not need the RenderContext or the egui and glyphon renderers
+pub struct HeadlessApp {
+    state: AppState,
+    adapter: Option<wgpu::Adapter>,
+}
+
+impl HeadlessApp {
+    // what the egui and glyphon renderers draw into
+    pub const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
+
+    pub fn new() -> Self {
+        let mut world = World::new();
+        insert_core_resources(&mut world);
+
+        Self {
+            state: AppState::from_world(world),
+            adapter: None,
+        }
+    }
+
+    // renders into a texture on the first adapter that has the features
+    // the engine needs, software ones last unless software_adapter is set
+    pub fn with_renderer(width: u32,
+        height: u32,
+        software_adapter: bool,
+    ) -> anyhow::Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+        let mut adapters = instance.enumerate_adapters(wgpu::Backends::all());
+        adapters.retain(|adapter| adapter.features().contains(REQUIRED_FEATURES));
+        adapters.sort_by_key(|adapter| {
+            let is_software = adapter.get_info().device_type == wgpu::DeviceType::Cpu;
+            is_software != software_adapter
+        });
+
+        let adapter = adapters.into_iter()
+            .next()
+            .with_context(|| format!("No graphics adapter supports {:?}", REQUIRED_FEATURES))?;
+
+        log::info!("Rendering headless on {}", adapter.get_info().name);
+
+        let (device, queue) = pollster::block_on(request_device(&adapter))?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: Self::TEXTURE_FORMAT,
+            width,
+            height,
+            // there is nothing to present to
+            present_mode: wgpu::PresentMode::AutoNoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Headless Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &[],
+        });
+
+        let mut world = World::new();
+        insert_core_resources(&mut world);
+        insert_render_resources(&mut world, &adapter, &device, &queue, &config, None);
+
+        world.insert_resource(RenderContext {
+            window: None,
+            config,
+            size: winit::dpi::PhysicalSize::new(width, height),
+            device,
+            queue,
+            target: RenderTarget::Texture(texture),
+        });
+
+        Ok(Self {
+            state: AppState::from_world(world),
+            adapter: Some(adapter),
+        })
+    }
+
+    pub fn add_screen(&mut self, screen: impl Screen) {
+        self.state.screen_server
+            .register_screen(screen);
+    }
+
+    pub fn world(&self) -> &World {
+        &self.state.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.state.world
+    }
+
+    pub fn has_renderer(&self) -> bool {
+        self.state.world.contains_resource::<RenderContext>()
+    }
+
+    // advances the clock by delta seconds instead of the real time,
+    // draws into the texture when there is a renderer
+    pub fn step(&mut self, delta: f32) {
+        self.state.step(delta);
+
+        if self.has_renderer() {
+            self.state.draw();
+
+            // nothing presents, so without waiting the frames would pile up
+            self.state.world.resource::<RenderContext>()
+                .device
+                .poll(wgpu::Maintain::Wait);
+        }
+    }
+
+    // reads back the last frame, blocking until the gpu is done
+    pub fn capture(&self) -> anyhow::Result<image::RgbaImage> {
+        let render_ctx = self.state.world.get_resource::<RenderContext>()
+            .context("Nothing was rendered, the app has no renderer")?;
+
+        let RenderTarget::Texture(texture) = &render_ctx.target else {
+            return Err(anyhow!("Only textures can be captured"));
+        };
+
+        let device = &render_ctx.device;
+        let width = texture.width();
+        let height = texture.height();
+        // rows of a buffer copy are padded
+        let bytes_per_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Headless Capture Buffer"),
+            size: bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Headless Capture Encoder"),
+        });
+
+        encoder.copy_texture_to_buffer(texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+
+        render_ctx.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let data = buffer.slice(..).get_mapped_range();
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for row in data.chunks(bytes_per_row as usize) {
+            // bgra to rgba
+            for pixel in row[..(width * 4) as usize].chunks(4) {
+                pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+            }
+        }
+
+        drop(data);
+        buffer.unmap();
+
+        image::RgbaImage::from_raw(width, height, pixels)
+            .context("The captured frame has the wrong size")
+    }
+}
+
+impl Default for HeadlessApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// what --headless runs, see LaunchArgs
+pub fn run(launch_args: &LaunchArgs) -> anyhow::Result<()> {
+    const WIDTH: u32 = 1280;
+    const HEIGHT: u32 = 720;
+    const DELTA: f32 = 1.0 / 60.0;
+
+    let mut app = if launch_args.no_render {
+        HeadlessApp::new()
+    } else {
+        HeadlessApp::with_renderer(WIDTH, HEIGHT, launch_args.software_adapter)
+            .context("Could not create a renderer, --no-render runs without one")?
+    };
+    app.add_screen(MenuScreen::default());
+    app.add_screen(GameScreen::default());
+
+    // there is nobody to press play in the menu
+    app.world_mut()
+        .insert_resource(GameState::Game);
+
+    if let Some(flythrough_args) = &launch_args.flythrough {
+        // without a renderer the report only times the updates
+        let adapter_name = app.adapter.as_ref()
+            .map_or_else(|| "none".to_string(), |adapter| adapter.get_info().name);
+        start_flythrough(&mut app.state.world, flythrough_args, adapter_name);
+    }
+
+    // a flythrough decides itself when it is done
+    let mut frame_idx = 0;
+    loop {
+        app.step(DELTA);
+        frame_idx += 1;
+
+        let flythrough = app.world().get_resource::<Flythrough>();
+        let is_done = match flythrough {
+            Some(flythrough) => flythrough.is_finished(),
+            None => frame_idx >= launch_args.frames,
+        };
+
+        if is_done {
+            break;
+        }
+    }
+
+    log::info!("Ran {} frames headless", frame_idx);
+
+    if let Some(screenshot) = &launch_args.screenshot {
+        app.capture()?
+            .save(screenshot)
+            .with_context(|| format!("Could not save {}", screenshot.display()))?;
+        log::info!("Saved the last frame to {}", screenshot.display());
+    }
+
+    Ok(())
+}
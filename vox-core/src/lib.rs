@@ -5,12 +5,15 @@ pub mod resources;
 pub mod screens;
 pub mod asset;
 pub mod world_ext;
+pub mod commands_ext;
 pub mod pass_ext;
 pub mod device_ext;
 pub mod voxel_position;
 pub mod voxel_registry;
+pub mod cli;
+#[cfg(not(target_arch="wasm32"))]
+pub mod headless;
 
-use std::borrow::BorrowMut;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -18,9 +21,9 @@ use bevy_ecs::world::Mut;
 use bevy_ecs::world::World;
 use render::model::*;
 use resources::asset_server::AssetServer;
-use resources::egui_renderer::EguiRenderer;
+use resources::egui_renderer::{draw_egui, EguiRenderer};
 use resources::game_state::GameState;
-use resources::glyphon_renderer::GlyphonRenderer;
+use resources::glyphon_renderer::{draw_glyphon_labels, GlyphonRenderer};
 use resources::render_server::RenderServer;
 use resources::screen_server::ScreenServer;
 use screens::game::GameScreen;
@@ -30,8 +33,26 @@ use render::texture::*;
 use render::instance_data::*;
 
 use resources::default_pipeline::DefaultPipeline;
+use resources::sky_pipeline::SkyPipeline;
+use resources::world_time::WorldTime;
+use resources::fog::Fog;
+use resources::msaa::{self, Msaa};
+use resources::debug_pipeline::DebugPipeline;
+use resources::debug_view::DebugView;
+use resources::gizmo_pipeline::GizmoPipeline;
+use resources::gizmos::Gizmos;
+use resources::frame_stats::FrameStats;
+use resources::flythrough::Flythrough;
+use resources::time::Time;
+use cli::{FlythroughArgs, LaunchArgs};
+use resources::render_graph::RenderGraph;
+use render::render_graph::{RenderGraphPass, SlotId, TransientTexture};
+use resources::post_process::PostProcessSettings;
+use resources::post_process_pipeline::{draw_post_process, PostProcessPipeline};
+#[cfg(all(debug_assertions, not(target_arch="wasm32")))]
+use resources::hot_reload::{self, HotReload};
 use resources::frame_context::FrameContext;
-use resources::render_context::RenderContext;
+use resources::render_context::{RenderContext, RenderTarget};
 use resources::input::InputRes;
 use resources::input::KeyState;
 use resources::mouse::MouseRes;
@@ -50,6 +71,8 @@ use wasm_bindgen::prelude::*;
 use world_ext::WorldExt;
 
 const SIM_DT: f32 = 1.0/144.0;
+// frames longer than this are not caught up with fixed updates
+const MAX_FRAME_DT: f32 = 0.25;
 
 struct AppState {
     delta_time: Instant,
@@ -60,7 +83,7 @@ struct AppState {
 }
 
 impl AppState {
-    async fn new(window: Arc<Window>) -> Self {
+    async fn new(window: Arc<Window>, launch_args: &LaunchArgs) -> Self {
         let size = window.inner_size();
 
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
@@ -77,18 +100,11 @@ impl AppState {
         let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::default(),
             compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
+            force_fallback_adapter: launch_args.software_adapter,
         }).await.unwrap();
 
-        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
-            required_features: Features::POLYGON_MODE_LINE | Features::MULTI_DRAW_INDIRECT | Features::INDIRECT_FIRST_INSTANCE,
-            #[cfg(not(target_arch="wasm32"))]
-            required_limits: wgpu::Limits::default(),
-            #[cfg(target_arch="wasm32")]
-            required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
-            memory_hints: wgpu::MemoryHints::Performance,
-            label: None,
-        }, None).await.unwrap();
+        let (device, queue) = request_device(&adapter).await
+            .unwrap();
 
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps.formats.iter()
@@ -101,7 +117,11 @@ impl AppState {
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: surface_caps.present_modes[0],
+            // a flythrough measures frame times, vsync would cap them
+            present_mode: match launch_args.flythrough {
+                Some(_) => wgpu::PresentMode::AutoNoVsync,
+                None => surface_caps.present_modes[0],
+            },
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
@@ -109,38 +129,34 @@ impl AppState {
 
         surface.configure(&device, &config);
 
-        let depth_texture = Texture::create_depth_texture(&device, &config, "depth_texture");
-
         let mut world = World::new();
-        world.init_resource::<InputRes>();
-        world.init_resource::<MouseRes>();
-        world.init_resource::<GameState>();
-        world.init_resource::<AssetServer>();
-        world.init_resource::<RenderServer>();
-
-        let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
-        let glyphon_renderer = GlyphonRenderer::new(&device, &queue);
-        let egui_renderer = EguiRenderer::new(&device, &window);
-        
-        world.insert_resource(egui_renderer);
-        world.insert_resource(glyphon_renderer);
-
-        world.insert_resource(
-            DefaultPipeline::new(&device,
-                &shader,
-                &config
-        ));
+        insert_core_resources(&mut world);
+        insert_render_resources(&mut world, &adapter, &device, &queue, &config, Some(&window));
+
+        if let Some(flythrough_args) = &launch_args.flythrough {
+            start_flythrough(&mut world, flythrough_args, adapter.get_info().name);
+        }
+
+        // debug builds pick up changes to res/ and shaders/
+        #[cfg(all(debug_assertions, not(target_arch="wasm32")))]
+        match HotReload::new() {
+            Ok(hot_reload) => world.insert_resource(hot_reload),
+            Err(e) => log::warn!("Could not start hot reloading: {}", e),
+        }
 
         world.insert_resource(RenderContext {
-            window,
+            window: Some(window),
             config,
             size,
             device,
             queue,
-            surface,
-            depth_texture,
+            target: RenderTarget::Surface(surface),
         });
 
+        Self::from_world(world)
+    }
+
+    fn from_world(world: World) -> Self {
         let delta_time = Instant::now();
         let accumulator = 0.0;
 
@@ -153,6 +169,201 @@ impl AppState {
             screen_server,
         }
     }
+
+    // runs the fixed updates the delta adds up to, then one update
+    fn step(&mut self, delta: f32) {
+        let delta = delta.min(MAX_FRAME_DT);
+        self.accumulator += delta;
+        self.world.resource_mut::<Time>()
+            .advance(delta);
+
+        while self.accumulator >= SIM_DT {
+            self.fixed_update();
+            self.accumulator -= SIM_DT;
+        }
+
+        let alpha = self.accumulator / SIM_DT;
+        self.world.resource_mut::<Time>()
+            .set_alpha(alpha);
+
+        self.update();
+    }
+
+    fn fixed_update(&mut self) {
+        self.screen_server.fixed_update(&mut self.world);
+    }
+
+    fn update(&mut self) {
+        let world = &mut self.world;
+
+        self.screen_server.update(world);
+
+        // headless worlds can run without a renderer
+        if world.contains_resource::<RenderContext>() {
+            msaa::apply_changes(world);
+        }
+
+        #[cfg(all(debug_assertions, not(target_arch="wasm32")))]
+        hot_reload::reload_changed(world);
+
+        // assets dropped by the screens are freed here
+        world.resource_mut::<AssetServer>()
+            .unload_unused();
+    }
+
+    // TODO: make this code easier to read
+    fn draw(&mut self) {
+        let world = &mut self.world;
+
+        world.resource_scope(|world: &mut World, mut frame_stats: Mut<FrameStats>| {
+            frame_stats.begin_frame(world.resource::<RenderServer>());
+        });
+
+        let render_ctx = world.render_context();
+        let window = render_ctx.window.clone();
+
+        let frame_ctx = FrameContext::new(render_ctx, None);
+        world.insert_resource(frame_ctx);
+
+        world.egui_renderer_mut()
+            .begin_frame(window.as_deref());
+
+        self.screen_server.draw(world);
+
+        world.resource_scope(|world: &mut World, mut render_graph: Mut<RenderGraph>| {
+            if let Err(e) = render_graph.execute(world) {
+                log::error!("Could not run the render graph: {}", e);
+            }
+        });
+
+        // gizmos are pushed again every frame, also when no pass drew them
+        if let Some(mut gizmos) = world.get_resource_mut::<Gizmos>() {
+            gizmos.clear();
+        }
+
+        let frame_ctx = world
+            .remove_resource::<FrameContext>()
+            .unwrap();
+
+        let render_ctx = world.render_context();
+        let buffers: Vec<wgpu::CommandBuffer> = frame_ctx
+            .encoders
+            .into_iter()
+            .map(|encoder| {
+                encoder.finish()
+            })
+            .collect();
+
+        render_ctx.queue.submit(buffers);
+        if let Some(output) = frame_ctx.output {
+            output.present();
+        }
+
+        world.resource_mut::<FrameStats>()
+            .end_schedules();
+    }
+}
+
+const REQUIRED_FEATURES: Features = Features::POLYGON_MODE_LINE
+    .union(Features::MULTI_DRAW_INDIRECT)
+    .union(Features::INDIRECT_FIRST_INSTANCE);
+
+async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+    // lets Msaa offer the sample counts beyond 1x and 4x
+    let optional_features = adapter.features() & Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+
+    adapter.request_device(&wgpu::DeviceDescriptor {
+        required_features: REQUIRED_FEATURES | optional_features,
+        #[cfg(not(target_arch="wasm32"))]
+        required_limits: wgpu::Limits::default(),
+        #[cfg(target_arch="wasm32")]
+        required_limits: wgpu::Limits::downlevel_webgl2_defaults(),
+        memory_hints: wgpu::MemoryHints::Performance,
+        label: None,
+    }, None).await
+}
+
+// everything the update schedules need, with or without a renderer
+fn insert_core_resources(world: &mut World) {
+    world.init_resource::<InputRes>();
+    world.init_resource::<MouseRes>();
+    world.init_resource::<GameState>();
+    world.init_resource::<AssetServer>();
+    world.init_resource::<RenderServer>();
+    world.init_resource::<DebugView>();
+    world.init_resource::<Gizmos>();
+    world.init_resource::<FrameStats>();
+    world.init_resource::<WorldTime>();
+    world.insert_resource(Time::new(SIM_DT));
+    world.init_resource::<Fog>();
+}
+
+// the RenderContext is inserted by the caller, it owns the device
+fn insert_render_resources(world: &mut World,
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    config: &wgpu::SurfaceConfiguration,
+    window: Option<&Window>,
+) {
+    let glyphon_renderer = GlyphonRenderer::new(device, queue);
+    let egui_renderer = EguiRenderer::new(device, window);
+
+    world.insert_resource(egui_renderer);
+    world.insert_resource(glyphon_renderer);
+
+    world.insert_resource(PostProcessSettings::for_surface(config.format));
+    world.insert_resource(
+        PostProcessPipeline::new(device,
+            queue,
+            config
+    ));
+
+    // screens add their own passes, post processing takes
+    // what they drew to SlotId::HDR and the overlays go on top
+    let mut render_graph = RenderGraph::default();
+    render_graph.add_texture(PostProcessPipeline::PING, TransientTexture::color(Texture::HDR_TEXTURE_FORMAT));
+    render_graph.add_texture(PostProcessPipeline::PONG, TransientTexture::color(Texture::HDR_TEXTURE_FORMAT));
+    render_graph.add_pass(RenderGraphPass::new("post_process", draw_post_process)
+        .reads(SlotId::HDR)
+        .writes(PostProcessPipeline::PING)
+        .writes(PostProcessPipeline::PONG)
+        .writes(SlotId::SURFACE)
+    ).unwrap();
+    render_graph.add_pass(RenderGraphPass::new("glyphon_labels", draw_glyphon_labels)
+        .modifies(SlotId::SURFACE)
+    ).unwrap();
+    render_graph.add_pass(RenderGraphPass::new("egui", draw_egui)
+        .modifies(SlotId::SURFACE)
+    ).unwrap();
+    world.insert_resource(render_graph);
+
+    // the pipelines and render graph are set up by msaa::apply_changes
+    let msaa = Msaa::new(adapter, device);
+    let default_pipeline = DefaultPipeline::new(device, msaa.sample_count());
+    world.insert_resource(DebugPipeline::new(device,
+        default_pipeline.camera_bind_group_layout(),
+        msaa.sample_count()
+    ));
+    world.insert_resource(GizmoPipeline::new(device,
+        default_pipeline.camera_bind_group_layout(),
+        msaa.sample_count()
+    ));
+    world.insert_resource(default_pipeline);
+    world.insert_resource(SkyPipeline::new(device, msaa.sample_count()));
+    world.insert_resource(msaa);
+}
+
+// straight into the game, with the sun standing still
+// adapter_name only ends up in the report
+fn start_flythrough(world: &mut World,
+    flythrough_args: &FlythroughArgs,
+    adapter_name: String,
+) {
+    world.insert_resource(Flythrough::new(flythrough_args.clone(), adapter_name));
+    world.insert_resource(GameState::Game);
+    world.resource_mut::<WorldTime>()
+        .set_paused(true);
 }
 
 #[derive(Default)]
@@ -160,6 +371,7 @@ pub struct App {
     window: Option<Arc<Window>>,
     state: Option<AppState>,
     screen_queue: Option<Vec<Box<dyn Screen>>>,
+    launch_args: LaunchArgs,
 }
 
 impl ApplicationHandler for App {
@@ -199,14 +411,9 @@ impl ApplicationHandler for App {
             _ => {}
         }
 
-        let world = self.state_mut()
-            .world
-            .borrow_mut();
-
-        let window = world.render_context()
-            .window.clone();
-
-        world.egui_renderer_mut()
+        let window = self.window.clone().unwrap();
+        self.state_mut().world
+            .egui_renderer_mut()
             .window_event(&window, &event);
     }
 
@@ -227,7 +434,15 @@ impl ApplicationHandler for App {
             return;
         }
 
-        let window = Arc::new(event_loop.create_window(WindowAttributes::default()).unwrap());
+        // a fixed size keeps flythrough runs comparable
+        let window_attributes = match self.launch_args.flythrough {
+            Some(_) => WindowAttributes::default()
+                .with_inner_size(winit::dpi::PhysicalSize::new(1280, 720))
+                .with_resizable(false),
+            None => WindowAttributes::default(),
+        };
+
+        let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
         //window.set_cursor_grab(CursorGrabMode::Locked)
         //    .or_else(|_e| window.set_cursor_grab(CursorGrabMode::Confined))
         //    .unwrap();
@@ -236,7 +451,7 @@ impl ApplicationHandler for App {
         self.window = Some(window.clone());
 
         #[cfg(not(target_arch = "wasm32"))]
-        let mut state = pollster::block_on(AppState::new(window));
+        let mut state = pollster::block_on(AppState::new(window, &self.launch_args));
 
         if let Some(screens) = self.screen_queue.take() {
             state.screen_server.register_screens(screens);
@@ -249,7 +464,16 @@ impl ApplicationHandler for App {
         self.start();
     }
 
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let is_flythrough_finished = self.state_ref().world
+            .get_resource::<Flythrough>()
+            .is_some_and(|flythrough| flythrough.is_finished());
+
+        if is_flythrough_finished {
+            event_loop.exit();
+            return;
+        }
+
         let window = self.window.as_ref().unwrap();
         window.request_redraw();
     }
@@ -264,8 +488,11 @@ impl App {
             ctx.size = new_size;
             ctx.config.width = new_size.width;
             ctx.config.height = new_size.height;
-            ctx.depth_texture = Texture::create_depth_texture(&ctx.device, &ctx.config, "depth_texture");
-            ctx.surface.configure(&ctx.device, &ctx.config);
+            // the render graph recreates the depth and (multisampled)
+            // color targets at the new size on the next frame
+            if let RenderTarget::Surface(surface) = &ctx.target {
+                surface.configure(&ctx.device, &ctx.config);
+            }
         }
     }
 
@@ -291,6 +518,7 @@ impl App {
             KeyCode::KeyA => input_res.left = KeyState::from(key_state),
             KeyCode::KeyS => input_res.backward = KeyState::from(key_state),
             KeyCode::KeyD => input_res.right = KeyState::from(key_state),
+            KeyCode::F3 => input_res.debug_view = KeyState::from(key_state),
             _ => {},
         }
     }
@@ -304,68 +532,14 @@ impl App {
     }
 
     fn redraw_requested(&mut self) {
-        {
-            let state = self.state_mut();
-            state.accumulator += state.delta_time
-                .elapsed()
-                .as_secs_f32();
-            state.delta_time = Instant::now();
-        }
-
-        // TODO: check the update loop
-        //while self.state_ref().accumulator >= SIM_DT {
-        //    print!("running update!");
-        //    self.update();
-        //    self.state_mut().accumulator -= SIM_DT;
-        //}
-
-        self.update();
-        self.draw();
-    }
-
-    fn update(&mut self) {
-        let state_mut = &mut self.state_mut();
-        let world = &mut state_mut.world;
-
-        state_mut.screen_server.update(world);
-    }
-
-    // TODO: make this code easier to read
-    fn draw(&mut self) {
-        let state_mut = &mut self.state_mut();
-        let world = &mut state_mut.world;
-        let render_ctx = world.render_context();
-
-        let frame_ctx = FrameContext::new(render_ctx, None);
-        world.insert_resource(frame_ctx);
-
-        state_mut.screen_server.draw(world);
-
-        let mut frame_ctx = world
-            .remove_resource::<FrameContext>()
-            .unwrap();
-
-        world.resource_scope(|world: &mut World, render_ctx: Mut<RenderContext>| {
-            world.glyphon_renderer_mut()
-                .draw(&render_ctx, &mut frame_ctx);
-
-            world.resource_scope(|world: &mut World, mut state: Mut<GameState>| {
-                world.egui_renderer_mut()
-                    .draw(&render_ctx, &mut frame_ctx, &mut state);
-            })
-        });
-
-        let render_ctx = world.render_context();
-        let buffers: Vec<wgpu::CommandBuffer> = frame_ctx
-            .encoders
-            .into_iter()
-            .map(|encoder| {
-                encoder.finish()
-            })
-            .collect();
+        let state = self.state_mut();
+        let delta = state.delta_time
+            .elapsed()
+            .as_secs_f32();
 
-        render_ctx.queue.submit(buffers);
-        frame_ctx.output.present();
+        state.delta_time = Instant::now();
+        state.step(delta);
+        state.draw();
     }
 
     fn state_ref(&self) -> &AppState {
@@ -400,6 +574,33 @@ pub fn run() {
         }
     }
 
+    #[cfg(not(target_arch="wasm32"))]
+    let launch_args = match LaunchArgs::parse(std::env::args().skip(1)) {
+        Ok(launch_args) if launch_args.show_help => {
+            println!("{}", cli::USAGE);
+            return;
+        },
+        Ok(launch_args) => launch_args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        },
+    };
+
+    #[cfg(target_arch="wasm32")]
+    let launch_args = LaunchArgs::default();
+
+    // no event loop, it would need a display
+    #[cfg(not(target_arch="wasm32"))]
+    if launch_args.headless {
+        if let Err(e) = headless::run(&launch_args) {
+            log::error!("Headless run failed: {:#}", e);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
     let event_loop = EventLoop::new().unwrap();
     event_loop.set_control_flow(ControlFlow::Poll);
     #[cfg(target_arch="wasm32")]
@@ -417,6 +618,9 @@ pub fn run() {
         builder = builder.with_canvas(Some(canvas));
     }
 
-    let mut app = App::default();
+    let mut app = App {
+        launch_args,
+        ..Default::default()
+    };
     let _ = event_loop.run_app(&mut app);
 }
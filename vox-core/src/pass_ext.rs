@@ -14,6 +14,10 @@ pub trait VoxDrawPassExt {
         material: &Material,
         camera_bind_group: &wgpu::BindGroup,
     );
+    // for passes binding their own groups, like
+    // DefaultPipeline::shadow_pass or the debug view
+    fn draw_mesh_geometry(&mut self, mesh: &Mesh);
+    fn draw_mesh_multi_indexed_geometry(&mut self, mesh: &MultiIndexedMesh);
 }
 
 impl VoxDrawPassExt for wgpu::RenderPass<'_> {
@@ -28,6 +32,10 @@ impl VoxDrawPassExt for wgpu::RenderPass<'_> {
         let num_indices = mesh.num_indices() as u32;
         let num_instances = mesh.num_instances() as u32;
 
+        if num_instances == 0 {
+            return;
+        }
+
         self.set_vertex_buffer(0, vertex_buffer.slice(..));
         self.set_vertex_buffer(1, instance_buffer.slice(..));
         self.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
@@ -57,4 +65,28 @@ impl VoxDrawPassExt for wgpu::RenderPass<'_> {
             draw_count
         );
     }
+
+    fn draw_mesh_geometry(&mut self, mesh: &Mesh) {
+        let num_indices = mesh.num_indices() as u32;
+        let num_instances = mesh.num_instances() as u32;
+
+        if num_instances == 0 {
+            return;
+        }
+
+        self.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+        self.set_vertex_buffer(1, mesh.instance_buffer().slice(..));
+        self.set_index_buffer(mesh.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+        self.draw_indexed(0..num_indices, 0, 0..num_instances);
+    }
+
+    fn draw_mesh_multi_indexed_geometry(&mut self, mesh: &MultiIndexedMesh) {
+        self.set_vertex_buffer(0, mesh.vertex_buffer().slice(..));
+        self.set_vertex_buffer(1, mesh.instance_buffer().slice(..));
+        self.set_index_buffer(mesh.index_buffer().slice(..), wgpu::IndexFormat::Uint32);
+        self.multi_draw_indexed_indirect(mesh.indirect_buffer(),
+            0,
+            mesh.draw_count()
+        );
+    }
 }
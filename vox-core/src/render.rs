@@ -1,7 +1,19 @@
 pub mod texture;
+pub mod texture_array;
 pub mod instance_data;      
 pub mod model;
+pub mod gltf_model;
 pub mod material;
+pub mod light;
+pub mod mipmap;
+pub mod shader;
+pub mod shadow_map;
+pub mod sky;
+pub mod fog;
+pub mod render_graph;
+pub mod post_process;
+pub mod debug_view;
+pub mod gizmo;
 pub mod mesh;
 pub mod vertex;
 pub mod phantom_mesh;
@@ -4,7 +4,7 @@ use binary_greedy_meshing::{self as bgm, CS_P3};
 use cgmath::{Quaternion, Vector3, Zero};
 use wgpu::util::DrawIndexedIndirectArgs;
 
-use crate::{render::{mesh::{AsMesh, MeshPosition}, multi_indexed_mesh::AsMultiIndexedMesh, face_orientation::FaceOrientation, vertex::{Index, Vertex}}, voxel_position::VoxelPosition, voxel_registry::{VoxelRegistry, VoxelType, VoxelTypeIdentifier}, InstanceData};
+use crate::{render::{mesh::{AsMesh, MeshPosition}, multi_indexed_mesh::AsMultiIndexedMesh, face_orientation::FaceOrientation, vertex::{Index, Vertex}}, resources::{render_server::MaterialId, voxel_atlas::VoxelAtlas}, voxel_position::VoxelPosition, voxel_registry::{VoxelRegistry, VoxelType, VoxelTypeIdentifier}, InstanceData};
 
 use super::face::FaceDescriptor;
 
@@ -14,128 +14,152 @@ const VERTICES: [Vertex ; 24] = [
     // UP
     Vertex {
         position: [0.0, 0.0, 1.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 1.0, 0.0],
         tex_coords: [0.0, 0.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
     },
     Vertex {
         position: [1.0, 0.0, 1.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 1.0, 0.0],
         tex_coords: [1.0, 0.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
     },
     Vertex {
         position: [1.0, 0.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 1.0, 0.0],
         tex_coords: [1.0, 1.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
     },
     Vertex {
         position: [0.0, 0.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 1.0, 0.0],
         tex_coords: [0.0, 1.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
     },
     // DOWN
     Vertex {
         position: [0.0, 0.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, -1.0, 0.0],
         tex_coords: [1.0, 0.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
     },
     Vertex {
         position: [-1.0, 0.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, -1.0, 0.0],
         tex_coords: [0.0, 0.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
     },
     Vertex {
         position: [-1.0, 0.0, 1.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, -1.0, 0.0],
         tex_coords: [0.0, 1.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
     },
     Vertex {
         position: [0.0, 0.0, 1.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, -1.0, 0.0],
         tex_coords: [1.0, 1.0],
+        tangent: [1.0, 0.0, 0.0, 1.0],
     },
     // RIGHT
     Vertex {
         position: [0.0, 0.0, 1.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [1.0, 0.0, 0.0],
         tex_coords: [0.0, 0.0],
+        tangent: [0.0, -1.0, 0.0, 1.0],
     },
     Vertex {
         position: [0.0, 0.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [1.0, 0.0, 0.0],
         tex_coords: [0.0, 1.0],
+        tangent: [0.0, -1.0, 0.0, 1.0],
     },
     Vertex {
         position: [0.0, -1.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [1.0, 0.0, 0.0],
         tex_coords: [1.0, 1.0],
+        tangent: [0.0, -1.0, 0.0, 1.0],
     },
     Vertex {
         position: [0.0, -1.0, 1.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [1.0, 0.0, 0.0],
         tex_coords: [1.0, 0.0],
+        tangent: [0.0, -1.0, 0.0, 1.0],
     },
     // LEFT
     Vertex {
         position: [0.0, 0.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [-1.0, 0.0, 0.0],
         tex_coords: [0.0, 0.0],
+        tangent: [0.0, 0.0, 1.0, 1.0],
     },
     Vertex {
         position: [0.0, 0.0, 1.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [-1.0, 0.0, 0.0],
         tex_coords: [1.0, 0.0],
+        tangent: [0.0, 0.0, 1.0, 1.0],
     },
     Vertex {
         position: [0.0, 1.0, 1.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [-1.0, 0.0, 0.0],
         tex_coords: [1.0, 1.0],
+        tangent: [0.0, 0.0, 1.0, 1.0],
     },
     Vertex {
         position: [0.0, 1.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [-1.0, 0.0, 0.0],
         tex_coords: [0.0, 1.0],
+        tangent: [0.0, 0.0, 1.0, 1.0],
     },
     // FRONT
     Vertex {
         position: [0.0, 0.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 0.0, 1.0],
         tex_coords: [0.0, 0.0],
+        tangent: [-1.0, 0.0, 0.0, -1.0],
     },
     Vertex {
         position: [-1.0, 0.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 0.0, 1.0],
         tex_coords: [1.0, 0.0],
+        tangent: [-1.0, 0.0, 0.0, -1.0],
     },
     Vertex {
         position: [-1.0, 1.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 0.0, 1.0],
         tex_coords: [1.0, 1.0],
+        tangent: [-1.0, 0.0, 0.0, -1.0],
     },
     Vertex {
         position: [0.0, 1.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 0.0, 1.0],
         tex_coords: [0.0, 1.0],
+        tangent: [-1.0, 0.0, 0.0, -1.0],
     },
     // BACK
     Vertex {
         position: [1.0, 0.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 0.0, -1.0],
         tex_coords: [0.0, 0.0],
+        tangent: [-1.0, 0.0, 0.0, 1.0],
     },
     Vertex {
         position: [0.0, 0.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 0.0, -1.0],
         tex_coords: [1.0, 0.0],
+        tangent: [-1.0, 0.0, 0.0, 1.0],
     },
     Vertex {
         position: [0.0, 1.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 0.0, -1.0],
         tex_coords: [1.0, 1.0],
+        tangent: [-1.0, 0.0, 0.0, 1.0],
     },
     Vertex {
         position: [1.0, 1.0, 0.0],
-        normal: [0.0, 0.0, 0.0],
+        normal: [0.0, 0.0, -1.0],
         tex_coords: [0.0, 1.0],
+        tangent: [-1.0, 0.0, 0.0, 1.0],
     },
 ];
 
@@ -181,7 +205,8 @@ impl AsMultiIndexedMesh for Chunk {
 
                     let instance_data = InstanceData {
                         position,
-                        rotation
+                        rotation,
+                        texture_layer: face.texture_layer,
                     };
 
                     vec.push(instance_data);
@@ -214,7 +239,7 @@ impl AsMultiIndexedMesh for Chunk {
     }
 
     fn material_id(&self) -> usize {
-        1
+        self.material_id
     }
 
     fn draw_count(&self) -> u32 {
@@ -228,6 +253,8 @@ pub struct Chunk {
     mesh_data: bgm::MeshData,
     face_map: HashMap<FaceDescriptor, Vec<MeshPosition>>,
     voxel_registry: VoxelRegistry,
+    // the material holding the VoxelAtlas texture
+    material_id: MaterialId,
 }
 
 impl Default for Chunk {
@@ -236,12 +263,14 @@ impl Default for Chunk {
         let mesh_data = bgm::MeshData::new();
         let face_map = HashMap::new();
         let voxel_registry = VoxelRegistry::default();
+        let material_id = 0;
 
         Self {
             voxels,
             mesh_data,
             face_map,
             voxel_registry,
+            material_id,
         }
     }
 }
@@ -268,7 +297,11 @@ impl Chunk {
         voxel_registry.get_type(voxel_id)
     }
 
-    pub fn update_faces(&mut self) {
+    pub fn set_material_id(&mut self, material_id: MaterialId) {
+        self.material_id = material_id;
+    }
+
+    pub fn update_faces(&mut self, voxel_atlas: &VoxelAtlas) {
         self.mesh_data.clear();
         self.face_map.clear();
         bgm::mesh(&self.voxels, &mut self.mesh_data, BTreeSet::default());
@@ -294,18 +327,15 @@ impl Chunk {
                 let width = width as u32;
                 let height = height as u32;
 
-                // TODO: this material id will not be used
-                // we are rendering this chunk indirectly
-                // so materials cannot be changed during rendering
-                // we need to store different arrays for each voxel type
-                // MAYBE MAKE THIS AN OPTION FOR THE MESH TO HAVE
-                let material_id = 0;
+                // the whole chunk is drawn with one material,
+                // so each face picks its texture from the atlas instead
+                let texture_layer = voxel_atlas.layer(voxel_id, orientation);
 
                 let descriptor = FaceDescriptor {
                     orientation,
                     width,
                     height,
-                    material_id,
+                    texture_layer,
                 };
 
                 match self.face_map.get_mut(&descriptor) {
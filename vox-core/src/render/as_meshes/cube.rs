@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::{render::vertex::Vertex, AsModel, Model, Texture};
+use crate::{render::vertex::{self, Vertex}, AsModel, Model, Texture};
 
 pub struct Cube {
     pub scale: f32,
@@ -30,134 +30,42 @@ pub struct Cube {
 //}
 
 fn cube_vertices(scale: f32) -> [Vertex ; 24] {
-    [
-        // Front face
-        Vertex {
-            position: [-scale, -scale, scale],
-            normal: [0.0, 0.0, scale],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, -scale, scale],
-            normal: [0.0, 0.0, -scale],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, scale, scale],
-            normal: [scale, 0.0, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [-scale, scale, scale],
-            normal: [-scale, 0.0, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        // Back face
-        Vertex {
-            position: [-scale, -scale, -scale],
-            normal: [0.0, scale, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [-scale, scale, -scale],
-            normal: [0.0, -scale, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, scale, -scale],
-            normal: [0.0, 0.0, scale],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, -scale, -scale],
-            normal: [0.0, 0.0, -scale],
-            tex_coords: [0.0, 0.5],
-        },
-        // Top face
-        Vertex {
-            position: [-scale, scale, -scale],
-            normal: [scale, 0.0, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [-scale, scale, scale],
-            normal: [-scale, 0.0, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, scale, scale],
-            normal: [0.0, scale, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, scale, -scale],
-            normal: [0.0, -scale, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        // Bottom face
-        Vertex {
-            position: [-scale, -scale, -scale],
-            normal: [0.0, 0.0, scale],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, -scale, -scale],
-            normal: [0.0, 0.0, -scale],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, -scale, scale],
-            normal: [scale, 0.0, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [-scale, -scale, scale],
-            normal: [-scale, 0.0, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        // Right face
-        Vertex {
-            position: [scale, -scale, -scale],
-            normal: [0.0, scale, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, scale, -scale],
-            normal: [0.0, -scale, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, scale, scale],
-            normal: [0.0, 0.0, scale],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [scale, -scale, scale],
-            normal: [0.0, 0.0, -scale],
-            tex_coords: [0.0, 0.5],
-        },
-        // Left face
-        Vertex {
-            position: [-scale, -scale, -scale],
-            normal: [scale, 0.0, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [-scale, -scale, scale],
-            normal: [-scale, 0.0, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [-scale, scale, scale],
-            normal: [0.0, scale, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-        Vertex {
-            position: [-scale, scale, -scale],
-            normal: [0.0, -scale, 0.0],
-            tex_coords: [0.0, 0.5],
-        },
-    ]
+    // the normal and the axes u and v run along, u x v is the
+    // normal so the corners are counter clockwise from outside
+    const FACES: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]), // front
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]), // back
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]), // top
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]), // bottom
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]), // right
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]), // left
+    ];
+
+    let corners = [
+        (-1.0, -1.0, [0.0, 1.0]),
+        (1.0, -1.0, [1.0, 1.0]),
+        (1.0, 1.0, [1.0, 0.0]),
+        (-1.0, 1.0, [0.0, 0.0]),
+    ];
+
+    let mut vertices = [Vertex::default(); 24];
+    for (face_idx, (normal, u, v)) in FACES.iter().enumerate() {
+        for (corner_idx, (u_sign, v_sign, tex_coords)) in corners.iter().enumerate() {
+            let position = [0, 1, 2].map(|axis| {
+                (normal[axis] + u[axis] * u_sign + v[axis] * v_sign) * scale
+            });
+
+            vertices[face_idx * 4 + corner_idx] = Vertex {
+                position,
+                tex_coords: *tex_coords,
+                normal: *normal,
+                ..Default::default()
+            };
+        }
+    }
+
+    vertex::compute_tangents(&mut vertices, &cube_indices());
+    vertices
 }
 
 fn cube_indices() -> [u32 ; 36] {
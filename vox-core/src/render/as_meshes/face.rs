@@ -1,9 +1,9 @@
-use crate::{render::{mesh::{AsMesh, MeshPosition}, face_orientation::FaceOrientation, vertex::{Index, Vertex}}, resources::render_server::MaterialId, InstanceData};
+use crate::{render::{mesh::{AsMesh, MeshPosition}, face_orientation::FaceOrientation, vertex::{Index, Vertex}}, InstanceData};
 
 #[derive(Hash, PartialEq, Eq, Debug)]
 pub struct FaceDescriptor {
     pub orientation: FaceOrientation,
     pub width: u32,
     pub height: u32,
-    pub material_id: MaterialId,
+    pub texture_layer: u32,
 }
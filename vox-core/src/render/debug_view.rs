@@ -0,0 +1,68 @@
+use bytemuck::{Pod, Zeroable};
+
+// What the debug view draws over the scene
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DebugViewMode {
+    #[default]
+    Off,
+    Wireframe,
+    // one color per axis, darker for the negative side
+    FaceOrientation,
+    ChunkTint,
+    // the quads the greedy mesher produced
+    QuadOutlines,
+}
+
+impl DebugViewMode {
+    // must match the DEBUG_ constants in debug.wgsl
+    pub fn index(&self) -> u32 {
+        match self {
+            DebugViewMode::Off             => 0,
+            DebugViewMode::Wireframe       => 1,
+            DebugViewMode::FaceOrientation => 2,
+            DebugViewMode::ChunkTint       => 3,
+            DebugViewMode::QuadOutlines    => 4,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            DebugViewMode::Off             => DebugViewMode::Wireframe,
+            DebugViewMode::Wireframe       => DebugViewMode::FaceOrientation,
+            DebugViewMode::FaceOrientation => DebugViewMode::ChunkTint,
+            DebugViewMode::ChunkTint       => DebugViewMode::QuadOutlines,
+            DebugViewMode::QuadOutlines    => DebugViewMode::Off,
+        }
+    }
+}
+
+// one per draw, see DebugPipeline
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct DebugUniform {
+    pub color: [f32; 4],
+    pub mode: u32,
+    pub _padding: [u32; 3],
+}
+
+impl DebugUniform {
+    pub fn new(mode: DebugViewMode, color: [f32; 3]) -> Self {
+        Self {
+            color: [color[0], color[1], color[2], 1.0],
+            mode: mode.index(),
+            _padding: [0; 3],
+        }
+    }
+}
+
+// a bright, stable color for every id
+pub fn id_color(id: usize) -> [f32; 3] {
+    // golden ratio steps spread the hues evenly
+    let hue = (id as f32 * 0.618_034).fract();
+    let channel = |offset: f32| {
+        let x = ((hue + offset).fract() * 6.0 - 3.0).abs() - 1.0;
+        0.25 + 0.75 * x.clamp(0.0, 1.0)
+    };
+
+    [channel(0.0), channel(2.0 / 3.0), channel(1.0 / 3.0)]
+}
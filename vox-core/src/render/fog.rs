@@ -0,0 +1,60 @@
+use bytemuck::{Pod, Zeroable};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FogMode {
+    Disabled,
+    // no fog before start, full fog after end
+    Linear {
+        start: f32,
+        end: f32,
+    },
+    Exponential {
+        density: f32,
+    },
+    ExponentialSquared {
+        density: f32,
+    },
+}
+
+impl FogMode {
+    // must match the FOG_ constants in common.wgsl
+    fn index(&self) -> u32 {
+        match *self {
+            FogMode::Disabled                 => 0,
+            FogMode::Linear { .. }             => 1,
+            FogMode::Exponential { .. }        => 2,
+            FogMode::ExponentialSquared { .. } => 3,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct FogUniform {
+    pub color: [f32; 3],
+    pub mode: u32,
+    pub start: f32,
+    pub end: f32,
+    pub density: f32,
+    pub _padding: f32,
+}
+
+impl FogUniform {
+    pub fn new(mode: FogMode, color: [f32; 3]) -> Self {
+        let (start, end, density) = match mode {
+            FogMode::Disabled => (0.0, 0.0, 0.0),
+            FogMode::Linear { start, end } => (start, end, 0.0),
+            FogMode::Exponential { density }
+                | FogMode::ExponentialSquared { density } => (0.0, 0.0, density),
+        };
+
+        Self {
+            color,
+            mode: mode.index(),
+            start,
+            end,
+            density,
+            ..Zeroable::zeroed()
+        }
+    }
+}
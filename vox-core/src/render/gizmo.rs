@@ -0,0 +1,32 @@
+use bytemuck::{Pod, Zeroable};
+
+// every two vertices make up a line, see GizmoPipeline
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct GizmoVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+}
+
+impl GizmoVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            step_mode: wgpu::VertexStepMode::Vertex,
+            array_stride: std::mem::size_of::<GizmoVertex>() as wgpu::BufferAddress,
+            attributes: &[
+                // position
+                wgpu::VertexAttribute {
+                    shader_location: 0,
+                    offset: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                // color
+                wgpu::VertexAttribute {
+                    shader_location: 1,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use cgmath::{Matrix4, SquareMatrix};
+
+use crate::{asset::{handle::Handle, AssetError, AssetLoader, LoadContext}, Texture};
+
+use super::{material::Material, model::{Model, ModelMaterial, ModelMesh}, vertex::{self, Vertex}};
+
+pub struct GltfLoader;
+
+impl AssetLoader for GltfLoader {
+    type Asset = Model;
+
+    fn extensions(&self) -> &[&str] {
+        &["gltf", "glb"]
+    }
+
+    fn load(&self, bytes: &[u8], ctx: &mut LoadContext) -> Result<Model, AssetError> {
+        Model::load_gltf(bytes, ctx)
+    }
+}
+
+impl Model {
+    pub fn load_gltf(bytes: &[u8], ctx: &mut LoadContext) -> Result<Model, AssetError> {
+        let gltf::Gltf { document, mut blob } = gltf::Gltf::from_slice(bytes)
+            .map_err(|e| ctx.error(e))?;
+
+        let buffers = document.buffers()
+            .map(|buffer| {
+                let mut data = match buffer.source() {
+                    gltf::buffer::Source::Bin => blob.take()
+                        .ok_or_else(|| ctx.error("Missing GLB binary chunk"))?,
+                    gltf::buffer::Source::Uri(uri) => read_uri(ctx, uri)?,
+                };
+
+                // the glb chunk can be padded past the buffer length
+                data.truncate(buffer.length());
+                Ok(data)
+            }).collect::<Result<Vec<_>, AssetError>>()?;
+
+        let mut materials = document.materials()
+            .map(|material| load_material(ctx, &buffers, &material))
+            .collect::<Result<Vec<_>, AssetError>>()?;
+
+        // primitives without a material use the gltf default material
+        let default_material_idx = materials.len();
+        materials.push(ModelMaterial {
+            diffuse_texture: Texture::debug(ctx.asset_server, ctx.device, ctx.queue)?,
+            normal_texture: Texture::flat_normal(ctx.asset_server, ctx.device, ctx.queue)?,
+            base_color: Material::WHITE,
+        });
+
+        let scene = document.default_scene()
+            .or_else(|| document.scenes().next())
+            .ok_or_else(|| ctx.error("No scene found"))?;
+
+        let mut meshes = Vec::new();
+        for node in scene.nodes() {
+            load_node(ctx, &buffers, &node, Matrix4::identity(), default_material_idx, &mut meshes)?;
+        }
+
+        Ok(Model::new(ctx.file_name, meshes, materials))
+    }
+}
+
+fn load_node(ctx: &LoadContext,
+    buffers: &[Vec<u8>],
+    node: &gltf::Node,
+    parent_transform: Matrix4<f32>,
+    default_material_idx: usize,
+    meshes: &mut Vec<ModelMesh>,
+) -> Result<(), AssetError> {
+    let transform = parent_transform * Matrix4::from(node.transform().matrix());
+
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                log::warn!("Skipping non triangle primitive in {}", ctx.file_name);
+                continue;
+            }
+
+            let reader = primitive.reader(|buffer| {
+                buffers.get(buffer.index())
+                    .map(Vec::as_slice)
+            });
+
+            let positions = reader.read_positions()
+                .ok_or_else(|| ctx.error("Primitive without positions"))?;
+
+            let mut vertices = positions
+                .map(|position| Vertex {
+                    position,
+                    ..Default::default()
+                }).collect::<Vec<_>>();
+
+            if let Some(normals) = reader.read_normals() {
+                vertices.iter_mut()
+                    .zip(normals)
+                    .for_each(|(vertex, normal)| vertex.normal = normal);
+            }
+
+            if let Some(tex_coords) = reader.read_tex_coords(0) {
+                vertices.iter_mut()
+                    .zip(tex_coords.into_f32())
+                    .for_each(|(vertex, tex_coords)| vertex.tex_coords = tex_coords);
+            }
+
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..vertices.len() as u32).collect(),
+            };
+
+            match reader.read_tangents() {
+                Some(tangents) => vertices.iter_mut()
+                    .zip(tangents)
+                    .for_each(|(vertex, tangent)| vertex.tangent = tangent),
+                None => vertex::compute_tangents(&mut vertices, &indices),
+            }
+
+            let material_idx = primitive.material()
+                .index()
+                .unwrap_or(default_material_idx);
+
+            meshes.push(ModelMesh {
+                vertices,
+                indices,
+                material_idx,
+                transform,
+            });
+        }
+    }
+
+    for child in node.children() {
+        load_node(ctx, buffers, &child, transform, default_material_idx, meshes)?;
+    }
+
+    Ok(())
+}
+
+fn load_material(ctx: &mut LoadContext,
+    buffers: &[Vec<u8>],
+    material: &gltf::Material,
+) -> Result<ModelMaterial, AssetError> {
+    let pbr = material.pbr_metallic_roughness();
+
+    // glTF multiplies the factor with the texture, so
+    // materials without one get a white texture
+    let diffuse_texture = match pbr.base_color_texture() {
+        Some(info) => load_texture(ctx, buffers, &info.texture(), Texture::TEXTURE_FORMAT)?,
+        None => Texture::white(ctx.asset_server, ctx.device, ctx.queue)?,
+    };
+
+    let normal_texture = match material.normal_texture() {
+        Some(normal) => load_texture(ctx, buffers, &normal.texture(), Texture::NORMAL_TEXTURE_FORMAT)?,
+        None => Texture::flat_normal(ctx.asset_server, ctx.device, ctx.queue)?,
+    };
+
+    Ok(ModelMaterial {
+        diffuse_texture,
+        normal_texture,
+        base_color: pbr.base_color_factor(),
+    })
+}
+
+fn load_texture(ctx: &mut LoadContext,
+    buffers: &[Vec<u8>],
+    texture: &gltf::Texture,
+    format: wgpu::TextureFormat,
+) -> Result<Handle<Texture>, AssetError> {
+    let image = texture.source();
+    let name = match image.source() {
+        gltf::image::Source::Uri { uri, .. } => ctx.directory()
+            .join(uri)
+            .to_string_lossy()
+            .to_string(),
+        gltf::image::Source::View { .. } => format!("{}#image{}", ctx.file_name, image.index()),
+    };
+
+    let name = if format == Texture::NORMAL_TEXTURE_FORMAT {
+        Texture::normal_map_name(&name)
+    } else {
+        name
+    };
+
+    if let Some(texture) = ctx.asset_server.get::<Texture>(&name) {
+        return Ok(texture);
+    }
+
+    let bytes = match image.source() {
+        gltf::image::Source::Uri { uri, .. } => read_uri(ctx, uri)?,
+        gltf::image::Source::View { view, .. } => {
+            let buffer = buffers.get(view.buffer().index())
+                .ok_or_else(|| ctx.error("Image references a missing buffer"))?;
+
+            buffer.get(view.offset()..view.offset() + view.length())
+                .ok_or_else(|| ctx.error("Image view out of bounds"))?
+                .to_vec()
+        }
+    };
+
+    let texture = image::load_from_memory(&bytes)
+        .map_err(|e| ctx.error(e))
+        .and_then(|img| {
+            Texture::from_image_ex(ctx.device, ctx.queue, &img, &name, format)
+                .map_err(|e| ctx.error(e))
+        })?;
+
+    Ok(ctx.asset_server.insert(texture))
+}
+
+// buffer and image uris are relative to the gltf file
+fn read_uri(ctx: &LoadContext, uri: &str) -> Result<Vec<u8>, AssetError> {
+    if uri.starts_with("data:") {
+        return Err(ctx.error("Embedded data uris are not supported, export the model as .glb instead"));
+    }
+
+    let path = ctx.directory()
+        .join(uri);
+
+    ctx.read(&path.to_string_lossy())
+}
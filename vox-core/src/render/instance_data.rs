@@ -1,5 +1,5 @@
 use bytemuck::{Pod, Zeroable};
-use cgmath::{num_traits::zero, Quaternion, Vector3, Zero};
+use cgmath::{num_traits::zero, Matrix, Matrix3, Matrix4, Quaternion, SquareMatrix, Vector3, Zero};
 
 use super::{mesh::MeshPosition, face_orientation::FaceOrientation};
 
@@ -7,32 +7,37 @@ use super::{mesh::MeshPosition, face_orientation::FaceOrientation};
 pub struct InstanceData {
     pub position: Vector3<f32>,
     pub rotation: Quaternion<f32>,
+    // only read when drawing with a texture array
+    pub texture_layer: u32,
 }
 
 impl InstanceData {
     pub fn from_position(position: MeshPosition) -> Self {
         let position: Vector3<f32> = position.into();
         let rotation = Quaternion::zero();
+        let texture_layer = 0;
 
         Self {
             position,
             rotation,
+            texture_layer,
         }
     }
 
     pub fn from_rotation(rotation: Quaternion<f32>) -> Self {
         let position: Vector3<f32> = (0.0, 0.0, 0.0).into();
+        let texture_layer = 0;
 
         Self {
             position,
             rotation,
+            texture_layer,
         }
     }
 
     pub fn to_raw(&self) -> InstanceRaw {
-        InstanceRaw {
-            model: (cgmath::Matrix4::from_translation(self.position) * cgmath::Matrix4::from(self.rotation)).into(),
-        }
+        let model = Matrix4::from_translation(self.position) * Matrix4::from(self.rotation);
+        InstanceRaw::new(model, self.texture_layer)
     }
 }
 
@@ -40,9 +45,37 @@ impl InstanceData {
 #[derive(Copy, Clone, Pod, Zeroable, Debug)]
 pub struct InstanceRaw {
     model: [[f32; 4]; 4],
+    texture_layer: u32,
+    // the inverse transpose of the model's upper 3x3, keeps
+    // normals perpendicular under non-uniform scale
+    normal: [[f32; 3]; 3],
+}
+
+impl From<Matrix4<f32>> for InstanceRaw {
+    fn from(model: Matrix4<f32>) -> Self {
+        Self::new(model, 0)
+    }
 }
 
 impl InstanceRaw {
+    pub fn new(model: Matrix4<f32>, texture_layer: u32) -> Self {
+        let direction_matrix = Matrix3::from_cols(model.x.truncate(),
+            model.y.truncate(),
+            model.z.truncate(),
+        );
+
+        // a scale of 0 flattens the model, the normals do not matter then
+        let normal = direction_matrix.invert()
+            .map(|inverse| inverse.transpose())
+            .unwrap_or(direction_matrix);
+
+        Self {
+            model: model.into(),
+            texture_layer,
+            normal: normal.into(),
+        }
+    }
+
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
@@ -68,6 +101,26 @@ impl InstanceRaw {
                     offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
                     shader_location: 8,
                 },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Uint32,
+                    offset: std::mem::size_of::<[f32; 16]>() as wgpu::BufferAddress,
+                    shader_location: 9,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: std::mem::size_of::<[f32; 17]>() as wgpu::BufferAddress,
+                    shader_location: 10,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: std::mem::size_of::<[f32; 20]>() as wgpu::BufferAddress,
+                    shader_location: 11,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: std::mem::size_of::<[f32; 23]>() as wgpu::BufferAddress,
+                    shader_location: 12,
+                },
             ]
         }
     }
@@ -0,0 +1,45 @@
+use bytemuck::{Pod, Zeroable};
+use cgmath::{InnerSpace, Vector3};
+
+// A single directional light, shaded with Blinn-Phong
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct LightUniform {
+    // the direction the light travels in
+    pub direction: [f32; 3],
+    pub shininess: f32,
+    pub color: [f32; 3],
+    pub _padding: f32,
+    // reaches every face, also the ones turned away from the light
+    pub ambient: [f32; 3],
+    pub _padding_2: f32,
+}
+
+impl LightUniform {
+    pub fn new(direction: Vector3<f32>,
+        color: [f32; 3],
+        ambient: [f32; 3],
+        shininess: f32,
+    ) -> Self {
+        let direction = direction.normalize()
+            .into();
+
+        Self {
+            direction,
+            shininess,
+            color,
+            ambient,
+            ..Zeroable::zeroed()
+        }
+    }
+}
+
+impl Default for LightUniform {
+    fn default() -> Self {
+        Self::new(Vector3::new(-0.4, -1.0, -0.3),
+            [1.0, 1.0, 1.0],
+            [0.2, 0.2, 0.2],
+            32.0,
+        )
+    }
+}
@@ -1,44 +1,122 @@
-use core::panic;
-use std::sync::Arc;
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
 
-use crate::{resources::render_server::MaterialId, Texture};
+use crate::{asset::handle::Handle, resources::render_server::MaterialId, Texture};
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct MaterialUniform {
+    // multiplied with the diffuse texture
+    pub base_color: [f32; 4],
+}
 
 #[derive(Debug)]
 pub struct Material {
-    diffuse_texture: Arc<Texture>,
+    diffuse_texture: Handle<Texture>,
+    normal_texture: Handle<Texture>,
+    base_color: [f32; 4],
+    uniform_buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
     material_id: MaterialId,
 }
 
 // TODO: cache this
 impl Material {
-    pub fn new(diffuse_texture: Arc<Texture>,
+    pub const WHITE: [f32; 4] = [1.0; 4];
+
+    pub fn new(diffuse_texture: Handle<Texture>,
+        normal_texture: Handle<Texture>,
+        base_color: [f32; 4],
         material_id: MaterialId,
         device: &wgpu::Device,
     ) -> Self {
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Material Buffer"),
+            contents: bytemuck::bytes_of(&MaterialUniform { base_color }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = Self::create_bind_group(&diffuse_texture, &normal_texture, &uniform_buffer, device);
+
+        Material {
+            diffuse_texture,
+            normal_texture,
+            base_color,
+            uniform_buffer,
+            bind_group,
+            material_id,
+        }
+    }
+
+    // used when the textures are reloaded
+    pub fn set_textures(&mut self,
+        diffuse_texture: Handle<Texture>,
+        normal_texture: Handle<Texture>,
+        device: &wgpu::Device,
+    ) {
+        self.bind_group = Self::create_bind_group(&diffuse_texture, &normal_texture, &self.uniform_buffer, device);
+        self.diffuse_texture = diffuse_texture;
+        self.normal_texture = normal_texture;
+    }
+
+    // the diffuse texture can be an array (see VoxelAtlas),
+    // normal maps are always a plain 2d texture
+    pub fn create_bind_group_layout(device: &wgpu::Device,
+        diffuse_view_dimension: wgpu::TextureViewDimension,
+    ) -> wgpu::BindGroupLayout {
+        let texture_entry = |binding: u32, view_dimension: wgpu::TextureViewDimension| {
+            wgpu::BindGroupLayoutEntry {
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension,
+                },
+                count: None,
+                binding,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+            }
+        };
+
+        let sampler_entry = |binding: u32| {
+            wgpu::BindGroupLayoutEntry {
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+                binding,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+            }
+        };
+
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("texture_bind_group_layout"),
             entries: &[
+                texture_entry(0, diffuse_view_dimension),
+                sampler_entry(1),
+                texture_entry(2, wgpu::TextureViewDimension::D2),
+                sampler_entry(3),
                 wgpu::BindGroupLayoutEntry {
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
                     },
                     count: None,
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                    binding: 1,
+                    binding: 4,
                     visibility: wgpu::ShaderStages::FRAGMENT,
                 },
             ]
-        });
-        
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        })
+    }
+
+    fn create_bind_group(diffuse_texture: &Texture,
+        normal_texture: &Texture,
+        uniform_buffer: &wgpu::Buffer,
+        device: &wgpu::Device,
+    ) -> wgpu::BindGroup {
+        let bind_group_layout = Self::create_bind_group_layout(device,
+            diffuse_texture.view_dimension()
+        );
+
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Material Bind Group"),
             layout: &bind_group_layout,
             entries: &[
@@ -50,26 +128,39 @@ impl Material {
                     binding: 1,
                     resource: wgpu::BindingResource::Sampler(diffuse_texture.sampler()),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(normal_texture.view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(normal_texture.sampler()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
             ]
-        });
-
-        Material {
-            diffuse_texture,
-            bind_group,
-            material_id,
-        }
+        })
     }
 
     pub fn material_id(&self) -> MaterialId {
         self.material_id
     }
 
-    pub fn diffuse_texture(&self) -> Arc<Texture> {
+    pub fn diffuse_texture(&self) -> Handle<Texture> {
         self.diffuse_texture.clone()
     }
 
+    pub fn normal_texture(&self) -> Handle<Texture> {
+        self.normal_texture.clone()
+    }
+
+    pub fn base_color(&self) -> [f32; 4] {
+        self.base_color
+    }
+
     pub fn bind_group(&self) -> &wgpu::BindGroup {
         &self.bind_group
     }
 }
-
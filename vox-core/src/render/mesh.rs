@@ -1,3 +1,4 @@
+use cgmath::{Matrix4, SquareMatrix};
 use wgpu::util::DeviceExt;
 
 use crate::{device_ext::VoxDeviceExt, resources::render_server::{MaterialId, MeshId, ModelId}, InstanceData, InstanceRaw};
@@ -25,6 +26,8 @@ pub struct Mesh {
     material_id: MaterialId, 
     mesh_id: MeshId,
     model_id: Option<ModelId>,
+    // applied before the instance transform, used by model meshes
+    transform: Matrix4<f32>,
 }
 
 impl Mesh {
@@ -52,9 +55,18 @@ impl Mesh {
             material_id,
             mesh_id,
             model_id,
+            transform: Matrix4::identity(),
         }
     }
 
+    pub fn transform(&self) -> Matrix4<f32> {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix4<f32>) {
+        self.transform = transform;
+    }
+
     pub fn model_id(&self) -> &Option<ModelId> {
         &self.model_id
     }
@@ -86,5 +98,21 @@ impl Mesh {
     pub fn num_instances(&self) -> usize {
         self.num_instances
     }
+
+    pub fn write_instances(&mut self,
+        instances_raw: &[InstanceRaw],
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        let contents: &[u8] = bytemuck::cast_slice(instances_raw);
+
+        if contents.len() as wgpu::BufferAddress > self.instance_buffer.size() {
+            self.instance_buffer = device.compute_raw_instance_buffer(instances_raw);
+        } else if !contents.is_empty() {
+            queue.write_buffer(&self.instance_buffer, 0, contents);
+        }
+
+        self.num_instances = instances_raw.len();
+    }
 }
 
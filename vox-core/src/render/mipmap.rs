@@ -0,0 +1,74 @@
+use image::{Rgba, RgbaImage};
+
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+// the full chain down to 1x1, starting with the image itself
+pub fn generate_mips(img: RgbaImage, is_srgb: bool) -> Vec<RgbaImage> {
+    let level_count = mip_level_count(img.width(), img.height());
+    let mut levels = vec![img];
+
+    for _ in 1..level_count {
+        let previous = levels.last().unwrap();
+        let width = (previous.width() / 2).max(1);
+        let height = (previous.height() / 2).max(1);
+
+        let level = RgbaImage::from_fn(width, height, |x, y| {
+            box_filter(previous, x * 2, y * 2, is_srgb)
+        });
+
+        levels.push(level);
+    }
+
+    levels
+}
+
+// averages the 2x2 block at x, y, odd edges reuse the last pixel
+fn box_filter(img: &RgbaImage, x: u32, y: u32, is_srgb: bool) -> Rgba<u8> {
+    let max_x = img.width() - 1;
+    let max_y = img.height() - 1;
+
+    let mut sum = [0.0; 4];
+    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        let pixel = img.get_pixel((x + dx).min(max_x), (y + dy).min(max_y));
+
+        for channel in 0..4 {
+            let value = pixel[channel] as f32 / 255.0;
+            // color is averaged in linear space, alpha is always linear
+            sum[channel] += if is_srgb && channel < 3 {
+                srgb_to_linear(value)
+            } else {
+                value
+            };
+        }
+    }
+
+    let mut pixel = [0; 4];
+    for channel in 0..4 {
+        let mut value = sum[channel] / 4.0;
+        if is_srgb && channel < 3 {
+            value = linear_to_srgb(value);
+        }
+
+        pixel[channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+
+    Rgba(pixel)
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
@@ -1,18 +1,38 @@
-use std::{ops::Range, sync::Arc};
+use std::io::{BufReader, Cursor};
 
-use bevy_ecs::component::Component;
+use cgmath::{Matrix4, SquareMatrix};
 
-use crate::{asset::Asset, resources::{asset_server::AssetServer, render_server::{MaterialId, RenderServer}}, InstanceData, Texture};
+use crate::{asset::{handle::Handle, Asset, AssetError, AssetLoader, LoadContext}, Texture};
 
-use super::{material::Material, mesh::{AsMesh, Mesh}, phantom_mesh::PhantomMesh, vertex::Vertex};
+use super::{material::Material, mesh::AsMesh, vertex::{self, Index, Vertex}};
 
 pub trait AsModel {
     fn meshes(&self) -> Vec<Box<dyn AsMesh>>;
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
+pub struct ModelMesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<Index>,
+    // index into the model's materials
+    pub material_idx: usize,
+    // relative to the entity drawing the model
+    pub transform: Matrix4<f32>,
+}
+
+#[derive(Debug)]
+pub struct ModelMaterial {
+    pub diffuse_texture: Handle<Texture>,
+    // a flat normal when the material has no normal map
+    pub normal_texture: Handle<Texture>,
+    // multiplied with the diffuse texture
+    pub base_color: [f32; 4],
+}
+
+#[derive(Debug)]
 pub struct Model {
-    meshes: Vec<PhantomMesh>,
+    meshes: Vec<ModelMesh>,
+    materials: Vec<ModelMaterial>,
     name: String,
 }
 
@@ -22,49 +42,106 @@ impl Asset for Model {
     }
 }
 
-//TODO remove anyhow
+pub struct ObjLoader;
+
+impl AssetLoader for ObjLoader {
+    type Asset = Model;
+
+    fn extensions(&self) -> &[&str] {
+        &["obj"]
+    }
+
+    fn load(&self, bytes: &[u8], ctx: &mut LoadContext) -> Result<Model, AssetError> {
+        Model::load(bytes, ctx)
+    }
+}
+
 impl Model {
-    pub fn load(file_name: &str,
-        asset_server: &mut AssetServer,
-        render_server: &mut RenderServer,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue
-    ) -> Vec<PhantomMesh> {
-        let (models, materials_opt) = tobj::load_obj(file_name, &tobj::GPU_LOAD_OPTIONS)
-            .expect("Could not load file OBJ file");
-
-        let material_ids: Vec<MaterialId> = match materials_opt {
+    pub fn load(bytes: &[u8], ctx: &mut LoadContext) -> Result<Model, AssetError> {
+        let mut reader = BufReader::new(Cursor::new(bytes));
+
+        // mtl and texture paths are relative to the obj file
+        let directory = ctx.directory()
+            .to_path_buf();
+
+        let (tobj_models, tobj_materials) = tobj::load_obj_buf(&mut reader,
+            &tobj::GPU_LOAD_OPTIONS,
+            |mtl_path| {
+                let mtl_path = directory.join(mtl_path);
+                let data = ctx.read(&mtl_path.to_string_lossy())
+                    .map_err(|_| tobj::LoadError::OpenFileFailed)?;
+
+                tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(data)))
+            }
+        ).map_err(|e| ctx.error(e))?;
+
+        let mut materials = match tobj_materials {
             Ok(tobj_materials) => {
                 tobj_materials
                     .into_iter()
                     .map(|m| {
-                        let diffuse_texture_name = &m.diffuse_texture.unwrap();
-                        let diffuse_texture = asset_server
-                            .get_or_load(diffuse_texture_name, device, queue)
-                            .unwrap();
-
-                        render_server.push_material(diffuse_texture, device)
-                    }).collect::<Vec<_>>()
+                        let diffuse_texture = match m.diffuse_texture {
+                            Some(texture_name) => {
+                                let texture_path = directory.join(texture_name);
+                                ctx.get_or_load(&texture_path.to_string_lossy())?
+                            },
+                            None => Texture::debug(ctx.asset_server, ctx.device, ctx.queue)?,
+                        };
+
+                        let normal_texture = match m.normal_texture {
+                            Some(texture_name) => {
+                                let texture_path = directory.join(texture_name);
+                                Texture::get_or_load_normal_map(ctx.asset_server,
+                                    &texture_path.to_string_lossy(),
+                                    ctx.device,
+                                    ctx.queue,
+                                )?
+                            },
+                            None => Texture::flat_normal(ctx.asset_server, ctx.device, ctx.queue)?,
+                        };
+
+                        Ok(ModelMaterial {
+                            diffuse_texture,
+                            normal_texture,
+                            base_color: Material::WHITE,
+                        })
+                    }).collect::<Result<Vec<_>, AssetError>>()?
             },
-            Err(_) => {
-                let diffuse_texture = Texture::debug(asset_server, device, queue);
-                let material_id = render_server
-                    .push_material(diffuse_texture, device);
-
-                vec![material_id]
+            Err(e) => {
+                log::warn!("Could not load materials for {}: {}", ctx.file_name, e);
+                Vec::new()
             }
         };
 
-        let meshes = models.into_iter()
+        // meshes without a material fall back to the debug texture
+        if materials.is_empty() {
+            let diffuse_texture = Texture::debug(ctx.asset_server, ctx.device, ctx.queue)?;
+            let normal_texture = Texture::flat_normal(ctx.asset_server, ctx.device, ctx.queue)?;
+            materials.push(ModelMaterial {
+                diffuse_texture,
+                normal_texture,
+                base_color: Material::WHITE,
+            });
+        }
+
+        let meshes = tobj_models.into_iter()
             .map(|m| {
-                let vertices = (0..m.mesh.positions.len() / 3)
+                let mut vertices = (0..m.mesh.positions.len() / 3)
                     .map(|i| {
-                        let mut normals = [0.0, 0.0, 0.0];
-                        if !m.mesh.normals.is_empty() { 
-                            normals = [
-                                m.mesh.normals[i * 2],
-                                m.mesh.normals[i * 2 + 1],
-                                m.mesh.normals[i * 2 + 2],
+                        let mut normal = [0.0, 0.0, 0.0];
+                        if !m.mesh.normals.is_empty() {
+                            normal = [
+                                m.mesh.normals[i * 3],
+                                m.mesh.normals[i * 3 + 1],
+                                m.mesh.normals[i * 3 + 2],
+                            ];
+                        }
+
+                        let mut tex_coords = [0.0, 0.0];
+                        if !m.mesh.texcoords.is_empty() {
+                            tex_coords = [
+                                m.mesh.texcoords[i * 2],
+                                1.0 - m.mesh.texcoords[i * 2 + 1],
                             ];
                         }
 
@@ -74,28 +151,47 @@ impl Model {
                                 m.mesh.positions[i * 3 + 1],
                                 m.mesh.positions[i * 3 + 2],
                             ],
-                            tex_coords: [m.mesh.texcoords[i * 2], 1.0 - m.mesh.texcoords[i * 2 + 1]],
-                            normal: normals,
+                            tex_coords,
+                            normal,
+                            ..Default::default()
                         }
                     }).collect::<Vec<_>>();
 
-                let indices = m.mesh.indices;
+                vertex::compute_tangents(&mut vertices, &m.mesh.indices);
 
-                // TODO: make this position customizable
-                let instance_data = InstanceData::from_position((0.0, 0.0, 0.0));
-                let instances = vec![instance_data];
-                // we need to convert the meshes' material idxs to our own
-                // material ids
-                let material_id = material_ids[m.mesh.material_id.unwrap_or(0)];
+                let material_idx = m.mesh.material_id
+                    .filter(|idx| *idx < materials.len())
+                    .unwrap_or(0);
 
-                PhantomMesh {
+                ModelMesh {
                     vertices,
-                    indices,
-                    instances,
-                    material_id
+                    indices: m.mesh.indices,
+                    material_idx,
+                    transform: Matrix4::identity(),
                 }
             }).collect::<Vec<_>>();
 
-        meshes
+        Ok(Model::new(ctx.file_name, meshes, materials))
+    }
+
+    pub fn new(file_name: &str,
+        meshes: Vec<ModelMesh>,
+        materials: Vec<ModelMaterial>,
+    ) -> Self {
+        let name = file_name.to_string();
+
+        Self {
+            meshes,
+            materials,
+            name,
+        }
+    }
+
+    pub fn meshes(&self) -> &[ModelMesh] {
+        &self.meshes
+    }
+
+    pub fn materials(&self) -> &[ModelMaterial] {
+        &self.materials
     }
 }
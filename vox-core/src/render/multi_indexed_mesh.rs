@@ -22,6 +22,7 @@ pub struct MultiIndexedMesh {
     instance_buffer: wgpu::Buffer,
     indirect_indexed_buffer: wgpu::Buffer,
     draw_count: u32,
+    num_instances: usize,
     material_id: MaterialId,
     mesh_id: MultiIndexedMeshId,
     model_id: Option<ModelId>,
@@ -41,6 +42,7 @@ impl MultiIndexedMesh {
         let vertex_buffer = device.compute_vertex_buffer(vertices);
         let index_buffer = device.compute_index_buffer(indices);
         let instance_buffer = device.compute_instance_buffer(&instances);
+        let num_instances = instances.len();
         let indirect_indexed_buffer = device
             .compute_indirect_indexed_buffer(indirect_indexed_args);
 
@@ -53,6 +55,7 @@ impl MultiIndexedMesh {
             mesh_id,
             model_id,
             draw_count,
+            num_instances,
         }
     }
 
@@ -87,4 +90,8 @@ impl MultiIndexedMesh {
     pub fn draw_count(&self) -> u32 {
         self.draw_count
     }
+
+    pub fn num_instances(&self) -> usize {
+        self.num_instances
+    }
 }
@@ -0,0 +1,61 @@
+use bytemuck::{Pod, Zeroable};
+
+// The fullscreen effects between the scene and the surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostEffect {
+    Exposure,
+    Tonemapping,
+    ColorGrading,
+    Fxaa,
+    Gamma,
+}
+
+impl PostEffect {
+    // the order the stack runs them in
+    pub const ALL: [PostEffect; 5] = [
+        PostEffect::Exposure,
+        PostEffect::Tonemapping,
+        PostEffect::ColorGrading,
+        PostEffect::Fxaa,
+        PostEffect::Gamma,
+    ];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PostEffect::Exposure     => "Exposure",
+            PostEffect::Tonemapping  => "ACES Tonemapping",
+            PostEffect::ColorGrading => "Color Grading",
+            PostEffect::Fxaa         => "FXAA",
+            PostEffect::Gamma        => "Gamma",
+        }
+    }
+
+    // in post_process.wgsl
+    pub fn entry_point(&self) -> &'static str {
+        match self {
+            PostEffect::Exposure     => "fs_exposure",
+            PostEffect::Tonemapping  => "fs_tonemapping",
+            PostEffect::ColorGrading => "fs_color_grading",
+            PostEffect::Fxaa         => "fs_fxaa",
+            PostEffect::Gamma        => "fs_gamma",
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct PostProcessUniform {
+    // in stops, 0 keeps the scene as it is
+    pub exposure: f32,
+    pub gamma: f32,
+    // 0 ignores the LUT, 1 uses only the LUT
+    pub lut_strength: f32,
+    pub lut_size: f32,
+    // one over the surface size, used by FXAA
+    pub texel_size: [f32; 2],
+    pub _padding: [f32; 2],
+}
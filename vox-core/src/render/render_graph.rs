@@ -0,0 +1,139 @@
+use std::{error::Error, fmt::Display};
+
+use bevy_ecs::system::{BoxedSystem, IntoSystem};
+
+use crate::{resources::game_state::GameState, Texture};
+
+// Names a texture passes read from or write to, the graph orders
+// passes by these and allocates the ones declared as transient
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SlotId(pub &'static str);
+
+impl SlotId {
+    // the swapchain texture, presented at the end of the frame
+    pub const SURFACE: SlotId = SlotId("surface");
+    // the main depth buffer, sized like the surface
+    pub const DEPTH: SlotId = SlotId("depth");
+    // the scene before post processing, sized like the surface
+    pub const HDR: SlotId = SlotId("hdr");
+    // drawn to instead of HDR with MSAA, resolves into it
+    pub const HDR_MULTISAMPLED: SlotId = SlotId("hdr_multisampled");
+}
+
+// A texture the graph owns, it follows the surface size
+// and is only allocated while an active pass uses it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransientTexture {
+    pub format: wgpu::TextureFormat,
+    pub usage: wgpu::TextureUsages,
+    pub sample_count: u32,
+}
+
+impl TransientTexture {
+    pub fn color(format: wgpu::TextureFormat) -> Self {
+        Self {
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            sample_count: 1,
+        }
+    }
+
+    pub fn depth() -> Self {
+        Self {
+            format: Texture::DEPTH_TEXTURE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            sample_count: 1,
+        }
+    }
+
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotAccess {
+    // sampled, runs after everything else touching the slot
+    Read,
+    // cleared or overwritten, runs first
+    Write,
+    // drawn over, runs between the two in the order it was added
+    Modify,
+}
+
+// A system recording one or more render passes into the FrameContext
+pub struct RenderGraphPass {
+    pub(crate) name: &'static str,
+    pub(crate) slots: Vec<(SlotId, SlotAccess)>,
+    // None runs in every state
+    pub(crate) game_state: Option<GameState>,
+    pub(crate) system: BoxedSystem,
+    pub(crate) is_initialized: bool,
+}
+
+impl RenderGraphPass {
+    pub fn new<M>(name: &'static str,
+        system: impl IntoSystem<(), (), M>,
+    ) -> Self {
+        Self {
+            name,
+            slots: Vec::new(),
+            game_state: None,
+            system: Box::new(IntoSystem::into_system(system)),
+            is_initialized: false,
+        }
+    }
+
+    pub fn reads(self, slot: SlotId) -> Self {
+        self.access(slot, SlotAccess::Read)
+    }
+
+    pub fn writes(self, slot: SlotId) -> Self {
+        self.access(slot, SlotAccess::Write)
+    }
+
+    pub fn modifies(self, slot: SlotId) -> Self {
+        self.access(slot, SlotAccess::Modify)
+    }
+
+    pub fn in_state(mut self, game_state: GameState) -> Self {
+        self.game_state = Some(game_state);
+        self
+    }
+
+    fn access(mut self, slot: SlotId, access: SlotAccess) -> Self {
+        self.slots.push((slot, access));
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn slots(&self) -> &[(SlotId, SlotAccess)] {
+        &self.slots
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderGraphError {
+    // the passes that could not be ordered
+    Cycle(Vec<&'static str>),
+    DuplicatePass(&'static str),
+}
+
+impl Display for RenderGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderGraphError::Cycle(names) => {
+                write!(f, "Render passes depend on each other: {}", names.join(", "))
+            },
+            RenderGraphError::DuplicatePass(name) => {
+                write!(f, "Render pass {} was added twice", name)
+            },
+        }
+    }
+}
+
+impl Error for RenderGraphError {}
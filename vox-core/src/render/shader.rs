@@ -0,0 +1,240 @@
+use std::{borrow::Cow, collections::HashSet, error::Error, fmt::Display, sync::Arc};
+
+// shaders are compiled into the binary so they work on wasm too,
+// hot reloading reads the same files from disk instead
+const EMBEDDED_SHADERS: &[(&str, &str)] = &[
+    ("common.wgsl", include_str!("../shaders/common.wgsl")),
+    ("instance.wgsl", include_str!("../shaders/instance.wgsl")),
+    ("shader.wgsl", include_str!("../shaders/shader.wgsl")),
+    ("shadow.wgsl", include_str!("../shaders/shadow.wgsl")),
+    ("sky.wgsl", include_str!("../shaders/sky.wgsl")),
+    ("post_process.wgsl", include_str!("../shaders/post_process.wgsl")),
+    ("debug.wgsl", include_str!("../shaders/debug.wgsl")),
+    ("gizmo.wgsl", include_str!("../shaders/gizmo.wgsl")),
+];
+
+// Supports:
+// #include "file.wgsl" (every file is included once)
+// #define NAME
+// #ifdef NAME / #ifndef NAME / #else / #endif
+#[derive(Debug)]
+pub struct Shader {
+    file_name: String,
+    source: String,
+    // the file and line every line of the output came from
+    line_map: Vec<(Arc<str>, usize)>,
+}
+
+impl Shader {
+    pub fn embedded(file_name: &str, defines: &[&str]) -> Result<Shader, ShaderError> {
+        Self::preprocess(file_name, defines, |file_name| {
+            EMBEDDED_SHADERS.iter()
+                .find(|(name, _)| *name == file_name)
+                .map(|(_, source)| source.to_string())
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))
+        })
+    }
+
+    pub fn preprocess(file_name: &str,
+        defines: &[&str],
+        read: impl Fn(&str) -> std::io::Result<String>,
+    ) -> Result<Shader, ShaderError> {
+        let mut preprocessor = Preprocessor {
+            read: &read,
+            defines: defines.iter()
+                .map(|define| define.to_string())
+                .collect(),
+            included: HashSet::new(),
+            source: String::new(),
+            line_map: Vec::new(),
+        };
+
+        preprocessor.include(file_name)?;
+
+        Ok(Shader {
+            file_name: file_name.to_string(),
+            source: preprocessor.source,
+            line_map: preprocessor.line_map,
+        })
+    }
+
+    pub fn descriptor(&self) -> wgpu::ShaderModuleDescriptor<'_> {
+        wgpu::ShaderModuleDescriptor {
+            label: Some(&self.file_name),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&self.source)),
+        }
+    }
+
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    // wgpu reports "wgsl:LINE:COLUMN" (or the label instead of wgsl)
+    // for the preprocessed source, point those at the original files
+    pub fn map_error(&self, message: &str) -> String {
+        let patterns = [format!("{}:", self.file_name), "wgsl:".to_string()];
+        let mut mapped = String::new();
+        let mut rest = message;
+
+        // the earliest match wins so a label ending in wgsl is mapped once
+        while let Some((idx, pattern)) = patterns.iter()
+            .filter_map(|pattern| rest.find(pattern.as_str()).map(|idx| (idx, pattern)))
+            .min_by_key(|(idx, pattern)| (*idx, usize::MAX - pattern.len()))
+        {
+            mapped.push_str(&rest[..idx]);
+            rest = &rest[idx + pattern.len()..];
+
+            let digits = rest.find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+
+            let location = rest[..digits].parse::<usize>()
+                .ok()
+                .and_then(|line| self.line_map.get(line.wrapping_sub(1)));
+
+            match location {
+                Some((file_name, line)) if rest[digits..].starts_with(':') => {
+                    mapped.push_str(&format!("{}:{}", file_name, line));
+                    rest = &rest[digits..];
+                },
+                _ => mapped.push_str(pattern),
+            }
+        }
+
+        mapped.push_str(rest);
+        mapped
+    }
+}
+
+struct Preprocessor<'a> {
+    read: &'a dyn Fn(&str) -> std::io::Result<String>,
+    defines: HashSet<String>,
+    included: HashSet<String>,
+    source: String,
+    line_map: Vec<(Arc<str>, usize)>,
+}
+
+impl Preprocessor<'_> {
+    fn include(&mut self, file_name: &str) -> Result<(), ShaderError> {
+        if !self.included.insert(file_name.to_string()) {
+            return Ok(());
+        }
+
+        let source = (self.read)(file_name)
+            .map_err(|source| ShaderError::Io {
+                file_name: file_name.to_string(),
+                source,
+            })?;
+
+        let file: Arc<str> = file_name.into();
+        let syntax_error = |line: usize, message: &str| ShaderError::Syntax {
+            file_name: file_name.to_string(),
+            line,
+            message: message.to_string(),
+        };
+
+        // one entry per open #ifdef, true if its lines are kept
+        let mut conditions: Vec<bool> = Vec::new();
+
+        for (idx, line) in source.lines().enumerate() {
+            let line_number = idx + 1;
+            let is_active = conditions.iter().all(|active| *active);
+            let trimmed = line.trim();
+
+            let Some(directive) = trimmed.strip_prefix('#') else {
+                if is_active {
+                    self.source.push_str(line);
+                    self.source.push('\n');
+                    self.line_map.push((file.clone(), line_number));
+                }
+
+                continue;
+            };
+
+            let (name, argument) = directive.split_once(char::is_whitespace)
+                .map(|(name, argument)| (name, argument.trim()))
+                .unwrap_or((directive, ""));
+
+            match name {
+                "ifdef" | "ifndef" => {
+                    if argument.is_empty() {
+                        return Err(syntax_error(line_number, "missing define name"));
+                    }
+
+                    let is_defined = self.defines.contains(argument);
+                    conditions.push(is_defined == (name == "ifdef"));
+                },
+                "else" => {
+                    let condition = conditions.last_mut()
+                        .ok_or_else(|| syntax_error(line_number, "#else without #ifdef"))?;
+
+                    *condition = !*condition;
+                },
+                "endif" => {
+                    conditions.pop()
+                        .ok_or_else(|| syntax_error(line_number, "#endif without #ifdef"))?;
+                },
+                "define" if is_active => {
+                    if argument.is_empty() {
+                        return Err(syntax_error(line_number, "missing define name"));
+                    }
+
+                    self.defines.insert(argument.to_string());
+                },
+                "include" if is_active => {
+                    let include_name = argument.strip_prefix('"')
+                        .and_then(|argument| argument.strip_suffix('"'))
+                        .ok_or_else(|| syntax_error(line_number, "expected #include \"file.wgsl\""))?;
+
+                    self.include(include_name)?;
+                },
+                "define" | "include" => {},
+                _ => return Err(syntax_error(line_number, &format!("unknown directive #{}", name))),
+            }
+        }
+
+        if !conditions.is_empty() {
+            return Err(syntax_error(source.lines().count(), "missing #endif"));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ShaderError {
+    Io {
+        file_name: String,
+        source: std::io::Error,
+    },
+    Syntax {
+        file_name: String,
+        line: usize,
+        message: String,
+    },
+}
+
+impl Display for ShaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderError::Io { file_name, source } => {
+                write!(f, "Could not read shader {}: {}", file_name, source)
+            },
+            ShaderError::Syntax { file_name, line, message } => {
+                write!(f, "{}:{}: {}", file_name, line, message)
+            },
+        }
+    }
+}
+
+impl Error for ShaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ShaderError::Io { source, .. } => Some(source),
+            ShaderError::Syntax { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use cgmath::{EuclideanSpace, InnerSpace, Matrix4, MetricSpace, Point3, Rad, SquareMatrix, Transform, Vector3, Vector4};
+use wgpu::util::DeviceExt;
+
+use crate::{components::camerable::{CameraComponent, OPENGL_TO_WGPU_MATRIX}, Texture};
+
+// must match CASCADE_COUNT in common.wgsl
+pub const CASCADE_COUNT: usize = 3;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct ShadowUniform {
+    pub cascades: [[[f32; 4]; 4]; CASCADE_COUNT],
+    // where every cascade ends along camera_forward, w is unused
+    pub splits: [f32; 4],
+    pub camera_forward: [f32; 4],
+}
+
+// The sun's depth rendered once per cascade, every cascade covers
+// a slice of the camera frustum so close shadows stay sharp
+#[derive(Debug)]
+pub struct ShadowMap {
+    texture: Arc<Texture>,
+    // one layer of the texture each, rendered to by the shadow pass
+    cascade_views: Vec<wgpu::TextureView>,
+    cascade_buffers: Vec<wgpu::Buffer>,
+    cascade_bind_groups: Vec<wgpu::BindGroup>,
+    cascade_bind_group_layout: wgpu::BindGroupLayout,
+    // sampled by the main pass
+    shadow_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl ShadowMap {
+    // must match SHADOW_MAP_SIZE in common.wgsl
+    pub const SIZE: u32 = 2048;
+    // no shadows are drawn past this even if the camera sees further
+    pub const MAX_DISTANCE: f32 = 120.0;
+    // how far behind a cascade geometry can still cast into it
+    const CASTER_DISTANCE: f32 = 100.0;
+    // 0 splits the frustum evenly, 1 logarithmically
+    const SPLIT_LAMBDA: f32 = 0.6;
+
+    pub fn new(device: &wgpu::Device) -> Self {
+        let texture = Texture::create_depth_texture_ex(device,
+            Self::SIZE,
+            Self::SIZE,
+            CASCADE_COUNT as u32,
+            wgpu::TextureViewDimension::D2Array,
+            "Shadow Map",
+        );
+
+        let cascade_views = (0..CASCADE_COUNT as u32)
+            .map(|cascade| {
+                texture.texture().create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Shadow Cascade View"),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: cascade,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            }).collect::<Vec<_>>();
+
+        let cascade_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shadow Cascade Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let cascade_buffers = (0..CASCADE_COUNT)
+            .map(|_| {
+                let view_proj: [[f32; 4]; 4] = Matrix4::identity()
+                    .into();
+
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Shadow Cascade Buffer"),
+                    contents: bytemuck::bytes_of(&view_proj),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                })
+            }).collect::<Vec<_>>();
+
+        let cascade_bind_groups = cascade_buffers.iter()
+            .map(|buffer| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Shadow Cascade Bind Group"),
+                    layout: &cascade_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: buffer.as_entire_binding(),
+                        },
+                    ],
+                })
+            }).collect::<Vec<_>>();
+
+        let shadow_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shadow Buffer"),
+            contents: bytemuck::bytes_of(&ShadowUniform::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shadow Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Depth,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                    count: None,
+                },
+            ],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: shadow_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(texture.view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(texture.sampler()),
+                },
+            ],
+        });
+
+        Self {
+            texture,
+            cascade_views,
+            cascade_buffers,
+            cascade_bind_groups,
+            cascade_bind_group_layout,
+            shadow_buffer,
+            bind_group,
+            bind_group_layout,
+        }
+    }
+
+    // fits every cascade around its slice of the camera frustum
+    pub fn update(&self,
+        queue: &wgpu::Queue,
+        camera: &CameraComponent,
+        light_direction: Vector3<f32>,
+    ) {
+        let view = Matrix4::look_at_rh(camera.position, camera.target, camera.up);
+        let Some(inverse_view) = view.invert() else {
+            return;
+        };
+
+        let forward = (camera.target - camera.position)
+            .normalize();
+        let light_direction = light_direction.normalize();
+        let tan_half_fovy = Rad::from(cgmath::Deg(camera.fovy / 2.0)).0
+            .tan();
+
+        let near = camera.znear;
+        let far = camera.zfar.min(Self::MAX_DISTANCE);
+
+        let mut uniform = ShadowUniform::zeroed();
+        let mut cascade_near = near;
+
+        for cascade in 0..CASCADE_COUNT {
+            let t = (cascade + 1) as f32 / CASCADE_COUNT as f32;
+            let log_split = near * (far / near).powf(t);
+            let linear_split = near + (far - near) * t;
+            let cascade_far = Self::SPLIT_LAMBDA * log_split + (1.0 - Self::SPLIT_LAMBDA) * linear_split;
+
+            let corners = [cascade_near, cascade_far].into_iter()
+                .flat_map(|distance| {
+                    let half_height = distance * tan_half_fovy;
+                    let half_width = half_height * camera.aspect;
+
+                    [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(x, y)| {
+                        let corner = Vector4::new(x * half_width, y * half_height, -distance, 1.0);
+                        Point3::from_homogeneous(inverse_view * corner)
+                    })
+                }).collect::<Vec<_>>();
+
+            let view_proj = Self::cascade_view_proj(&corners, light_direction);
+            queue.write_buffer(&self.cascade_buffers[cascade], 0, bytemuck::bytes_of(&view_proj));
+
+            uniform.cascades[cascade] = view_proj;
+            uniform.splits[cascade] = cascade_far;
+            cascade_near = cascade_far;
+        }
+
+        uniform.camera_forward = [forward.x, forward.y, forward.z, 0.0];
+        queue.write_buffer(&self.shadow_buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    fn cascade_view_proj(corners: &[Point3<f32>],
+        light_direction: Vector3<f32>,
+    ) -> [[f32; 4]; 4] {
+        let up = if light_direction.y.abs() > 0.99 {
+            Vector3::unit_z()
+        } else {
+            Vector3::unit_y()
+        };
+
+        // a sphere keeps the cascade the same size while the camera turns
+        let center = Point3::centroid(corners);
+        let radius = corners.iter()
+            .map(|corner| corner.distance(center))
+            .fold(0.0, f32::max);
+        let radius = (radius * 16.0).ceil() / 16.0;
+
+        // moving in whole texels stops shadow edges from crawling
+        let texel_size = radius * 2.0 / Self::SIZE as f32;
+        let light_view = Matrix4::look_at_rh(Point3::origin(), Point3::from_vec(light_direction), up);
+        let mut light_center = light_view.transform_point(center);
+        light_center.x = (light_center.x / texel_size).floor() * texel_size;
+        light_center.y = (light_center.y / texel_size).floor() * texel_size;
+
+        let center = light_view.invert()
+            .map(|inverse| inverse.transform_point(light_center))
+            .unwrap_or(center);
+
+        let eye = center - light_direction * (radius + Self::CASTER_DISTANCE);
+        let view = Matrix4::look_at_rh(eye, center, up);
+        let proj = cgmath::ortho(-radius, radius,
+            -radius, radius,
+            0.0, radius * 2.0 + Self::CASTER_DISTANCE
+        );
+
+        (OPENGL_TO_WGPU_MATRIX * proj * view).into()
+    }
+
+    pub fn cascade_pass<'a>(&self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        cascade: usize,
+    ) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow Render Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.cascade_views[cascade],
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        })
+    }
+
+    pub fn cascade_bind_group(&self, cascade: usize) -> &wgpu::BindGroup {
+        &self.cascade_bind_groups[cascade]
+    }
+
+    pub fn cascade_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.cascade_bind_group_layout
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.bind_group_layout
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+}
@@ -0,0 +1,120 @@
+use bytemuck::{Pod, Zeroable};
+use cgmath::{Matrix4, SquareMatrix, Vector3, VectorSpace};
+
+use super::light::LightUniform;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct SkyUniform {
+    // turns screen positions back into view directions
+    pub inverse_view_proj: [[f32; 4]; 4],
+    // w is padding for every vector
+    pub sun_direction: [f32; 4],
+    pub zenith_color: [f32; 4],
+    pub horizon_color: [f32; 4],
+    pub sun_color: [f32; 4],
+    pub moon_color: [f32; 4],
+}
+
+impl SkyUniform {
+    pub fn new(view_proj: Matrix4<f32>,
+        sun_direction: Vector3<f32>,
+        colors: &SkyColors,
+    ) -> Self {
+        let inverse_view_proj = view_proj.invert()
+            .unwrap_or(Matrix4::identity())
+            .into();
+
+        Self {
+            inverse_view_proj,
+            sun_direction: sun_direction.extend(0.0).into(),
+            zenith_color: colors.zenith.extend(1.0).into(),
+            horizon_color: colors.horizon.extend(1.0).into(),
+            sun_color: colors.sun.extend(1.0).into(),
+            moon_color: colors.moon.extend(1.0).into(),
+        }
+    }
+}
+
+// Linear colors for a sun elevation, shared by the sky and the lighting
+#[derive(Debug, Clone, Copy)]
+pub struct SkyColors {
+    pub zenith: Vector3<f32>,
+    pub horizon: Vector3<f32>,
+    pub sun: Vector3<f32>,
+    pub moon: Vector3<f32>,
+    pub ambient: Vector3<f32>,
+}
+
+impl SkyColors {
+    const DAY_ZENITH: Vector3<f32> = Vector3::new(0.15, 0.35, 0.8);
+    const DAY_HORIZON: Vector3<f32> = Vector3::new(0.6, 0.75, 0.95);
+    const SUNSET_ZENITH: Vector3<f32> = Vector3::new(0.2, 0.22, 0.45);
+    const SUNSET_HORIZON: Vector3<f32> = Vector3::new(0.9, 0.45, 0.2);
+    const NIGHT_ZENITH: Vector3<f32> = Vector3::new(0.004, 0.006, 0.02);
+    const NIGHT_HORIZON: Vector3<f32> = Vector3::new(0.02, 0.03, 0.06);
+
+    const NOON_SUN: Vector3<f32> = Vector3::new(1.0, 0.96, 0.9);
+    const LOW_SUN: Vector3<f32> = Vector3::new(1.0, 0.5, 0.25);
+    const MOON: Vector3<f32> = Vector3::new(0.12, 0.15, 0.25);
+
+    // elevation is the y of the normalized sun direction
+    pub fn from_sun_elevation(elevation: f32) -> Self {
+        let day = smoothstep(-0.1, 0.3, elevation);
+        // peaks when the sun touches the horizon
+        let sunset = (1.0 - elevation.abs() / 0.3)
+            .clamp(0.0, 1.0);
+
+        let zenith = Self::NIGHT_ZENITH.lerp(Self::DAY_ZENITH, day)
+            .lerp(Self::SUNSET_ZENITH, sunset * 0.3);
+        let horizon = Self::NIGHT_HORIZON.lerp(Self::DAY_HORIZON, day)
+            .lerp(Self::SUNSET_HORIZON, sunset * 0.8);
+
+        let sun = Self::LOW_SUN.lerp(Self::NOON_SUN, smoothstep(0.0, 0.4, elevation))
+            * smoothstep(-0.05, 0.1, elevation);
+        let moon = Self::MOON * smoothstep(-0.05, 0.1, -elevation);
+
+        // the sky itself lights faces turned away from the sun
+        let ambient = zenith * 0.4 + Vector3::new(0.02, 0.02, 0.03);
+
+        Self {
+            zenith,
+            horizon,
+            sun,
+            moon,
+            ambient,
+        }
+    }
+
+    // the sun lights the world during the day, the moon at night,
+    // both fade out at the horizon so the switch is not visible
+    pub fn light(&self, sun_direction: Vector3<f32>, shininess: f32) -> LightUniform {
+        let (direction, color) = if sun_direction.y >= 0.0 {
+            (-sun_direction, self.sun)
+        } else {
+            (sun_direction, self.moon)
+        };
+
+        LightUniform::new(direction,
+            color.into(),
+            self.ambient.into(),
+            shininess,
+        )
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        wgpu::Color {
+            r: self.horizon.x as f64,
+            g: self.horizon.y as f64,
+            b: self.horizon.z as f64,
+            a: 1.0,
+        }
+    }
+}
+
+fn smoothstep(edge_0: f32, edge_1: f32, x: f32) -> f32 {
+    let t = ((x - edge_0) / (edge_1 - edge_0))
+        .clamp(0.0, 1.0);
+
+    t * t * (3.0 - 2.0 * t)
+}
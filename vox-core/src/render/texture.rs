@@ -1,13 +1,15 @@
 use std::sync::Arc;
 
-use image::GenericImageView;
-use crate::{asset::Asset, resources::asset_server::AssetServer, util::load_binary};
+use crate::{asset::{handle::Handle, source::AssetSource, Asset, AssetError, AssetLoader, LoadContext}, resources::asset_server::AssetServer};
+
+use super::mipmap;
 
 #[derive(Debug)]
 pub struct Texture {
     texture: wgpu::Texture,
     view: wgpu::TextureView,
     sampler: wgpu::Sampler,
+    view_dimension: wgpu::TextureViewDimension,
     name: String,
 }
 
@@ -17,17 +19,114 @@ impl Asset for Texture {
     }
 }
 
+pub struct TextureLoader;
+
+impl AssetLoader for TextureLoader {
+    type Asset = Texture;
+
+    fn extensions(&self) -> &[&str] {
+        &["png", "jpg", "jpeg"]
+    }
+
+    fn load(&self, bytes: &[u8], ctx: &mut LoadContext) -> Result<Texture, AssetError> {
+        Texture::from_bytes(ctx.device, ctx.queue, bytes, ctx.file_name)
+            .map_err(|e| ctx.error(e))
+    }
+}
+
 impl Texture {
     pub const DEPTH_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+    // the scene is drawn in this, see PostProcessPipeline
+    pub const HDR_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
     pub const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+    // normal maps store directions, not colors
+    pub const NORMAL_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
 
     pub fn debug(asset_server: &mut AssetServer,
         device: &wgpu::Device,
         queue: &wgpu::Queue
-    ) -> Arc<Texture> {
+    ) -> Result<Handle<Texture>, AssetError> {
         asset_server
             .get_or_load("debug.png", device, queue)
-            .unwrap()
+    }
+
+    // used by materials that only have a base color
+    pub fn white(asset_server: &mut AssetServer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue
+    ) -> Result<Handle<Texture>, AssetError> {
+        const FILE_NAME: &str = "white";
+
+        if let Some(texture) = asset_server.get::<Texture>(FILE_NAME) {
+            return Ok(texture);
+        }
+
+        let texture = Self::from_color(device, queue, [1.0; 4], FILE_NAME)
+            .map_err(|e| AssetError::Load {
+                file_name: FILE_NAME.to_string(),
+                source: e.into(),
+            })?;
+
+        Ok(asset_server.insert(texture))
+    }
+
+    // used by materials without a normal map
+    pub fn flat_normal(asset_server: &mut AssetServer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue
+    ) -> Result<Handle<Texture>, AssetError> {
+        const FILE_NAME: &str = "flat_normal";
+
+        if let Some(texture) = asset_server.get::<Texture>(FILE_NAME) {
+            return Ok(texture);
+        }
+
+        let pixel = image::Rgba([128, 128, 255, 255]);
+        let img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, pixel));
+        let texture = Self::from_image_ex(device, queue, &img, FILE_NAME, Self::NORMAL_TEXTURE_FORMAT)
+            .map_err(|e| AssetError::Load {
+                file_name: FILE_NAME.to_string(),
+                source: e.into(),
+            })?;
+
+        Ok(asset_server.insert(texture))
+    }
+
+    // the same image can be used as color and as data,
+    // so normal maps are stored under their own name
+    pub fn normal_map_name(file_name: &str) -> String {
+        format!("{}#linear", file_name)
+    }
+
+    pub fn get_or_load_normal_map(asset_server: &mut AssetServer,
+        file_name: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue
+    ) -> Result<Handle<Texture>, AssetError> {
+        match asset_server.get::<Texture>(&Self::normal_map_name(file_name)) {
+            Some(texture) => Ok(texture),
+            None => Self::load_normal_map(asset_server, file_name, device, queue),
+        }
+    }
+
+    // always reads the file, replacing any loaded version
+    pub fn load_normal_map(asset_server: &mut AssetServer,
+        file_name: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue
+    ) -> Result<Handle<Texture>, AssetError> {
+        let bytes = asset_server.read(file_name)?;
+        let name = Self::normal_map_name(file_name);
+
+        let texture = image::load_from_memory(&bytes)
+            .map_err(anyhow::Error::from)
+            .and_then(|img| Self::from_image_ex(device, queue, &img, &name, Self::NORMAL_TEXTURE_FORMAT))
+            .map_err(|e| AssetError::Load {
+                file_name: file_name.to_string(),
+                source: e.into(),
+            })?;
+
+        Ok(asset_server.insert(texture))
     }
 
     pub fn from_bytes(
@@ -40,49 +139,140 @@ impl Texture {
         Self::from_image(device, queue, &img, file_name)
     }
  
+    // a 1x1 texture for materials that only specify a linear color
+    pub fn from_color(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        color: [f32; 4],
+        file_name: &str,
+    ) -> anyhow::Result<Texture> {
+        let to_srgb = |c: f32| (c.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8;
+        let pixel = image::Rgba([
+            to_srgb(color[0]),
+            to_srgb(color[1]),
+            to_srgb(color[2]),
+            (color[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+        ]);
+
+        let img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, pixel));
+        Self::from_image(device, queue, &img, file_name)
+    }
+
     pub fn from_image(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         img: &image::DynamicImage,
         file_name: &str
     ) -> anyhow::Result<Texture> {
-        let rgba = img.to_rgba8();
-        let dimensions = img.dimensions();
+        Self::from_image_ex(device, queue, img, file_name, Self::TEXTURE_FORMAT)
+    }
+
+    pub fn from_image_ex(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img: &image::DynamicImage,
+        file_name: &str,
+        format: wgpu::TextureFormat,
+    ) -> anyhow::Result<Texture> {
+        let levels = mipmap::generate_mips(img.to_rgba8(), format.is_srgb());
+        Self::from_mips(device, queue, &levels, file_name, format)
+    }
+
+    fn from_mips(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        levels: &[image::RgbaImage],
+        file_name: &str,
+        format: wgpu::TextureFormat,
+    ) -> anyhow::Result<Texture> {
+        Self::from_layers_ex(device, queue, &[levels], wgpu::TextureViewDimension::D2, file_name, format)
+    }
+
+    // every layer holds its own mip chain, all layers must have the same size
+    pub fn from_layers(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layers: &[Vec<image::RgbaImage>],
+        file_name: &str,
+    ) -> anyhow::Result<Texture> {
+        let layers = layers.iter()
+            .map(Vec::as_slice)
+            .collect::<Vec<_>>();
+
+        Self::from_layers_ex(device, queue, &layers, wgpu::TextureViewDimension::D2Array, file_name, Self::TEXTURE_FORMAT)
+    }
+
+    fn from_layers_ex(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layers: &[&[image::RgbaImage]],
+        view_dimension: wgpu::TextureViewDimension,
+        file_name: &str,
+        format: wgpu::TextureFormat,
+    ) -> anyhow::Result<Texture> {
+        let first_layer = layers.first()
+            .filter(|levels| !levels.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("{} has no layers", file_name))?;
+
+        let dimensions = first_layer[0].dimensions();
+        let mip_level_count = first_layer.len();
+
+        let is_uniform = layers.iter()
+            .all(|levels| levels.len() == mip_level_count && levels[0].dimensions() == dimensions);
+
+        anyhow::ensure!(is_uniform, "{} has layers of different sizes", file_name);
  
         let size = wgpu::Extent3d {
             width: dimensions.0,
             height: dimensions.1,
-            depth_or_array_layers: 1,
+            depth_or_array_layers: layers.len() as u32,
         };
  
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some(file_name),
             size,
-            mip_level_count: 1,
+            mip_level_count: mip_level_count as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: Self::TEXTURE_FORMAT,
+            format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         });
+
+        for (layer, levels) in layers.iter().enumerate() {
+            for (mip_level, level) in levels.iter().enumerate() {
+                let (width, height) = level.dimensions();
+
+                queue.write_texture(
+                   wgpu::ImageCopyTexture {
+                       aspect: wgpu::TextureAspect::All,
+                       texture: &texture,
+                       mip_level: mip_level as u32,
+                       origin: wgpu::Origin3d {
+                           x: 0,
+                           y: 0,
+                           z: layer as u32,
+                       },
+                   }, 
+                   level,
+                   wgpu::ImageDataLayout {
+                       offset: 0,
+                       bytes_per_row: Some(4 * width),
+                       rows_per_image: Some(height),
+                   },
+                   wgpu::Extent3d {
+                       width,
+                       height,
+                       depth_or_array_layers: 1,
+                   },
+                );
+            }
+        }
  
-        queue.write_texture(
-           wgpu::ImageCopyTexture {
-               aspect: wgpu::TextureAspect::All,
-               texture: &texture,
-               mip_level: 0,
-               origin: wgpu::Origin3d::ZERO,
-           }, 
-           &rgba,
-           wgpu::ImageDataLayout {
-               offset: 0,
-               bytes_per_row: Some(4 * dimensions.0),
-               rows_per_image: Some(dimensions.1),
-           },
-           size,
-        );
- 
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(view_dimension),
+            ..Default::default()
+        });
  
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::Repeat,
@@ -90,7 +280,8 @@ impl Texture {
             address_mode_w: wgpu::AddressMode::Repeat,
             mag_filter: wgpu::FilterMode::Linear,
             min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            // blends between mips so distant terrain does not shimmer
+            mipmap_filter: wgpu::FilterMode::Linear,
             ..Default::default()
         });
 
@@ -100,16 +291,18 @@ impl Texture {
             texture,
             view,
             sampler,
+            view_dimension,
             name,
         })
     }
 
     pub fn load(
         file_name: &str,
+        source: &dyn AssetSource,
         device: &wgpu::Device,
         queue: &wgpu::Queue
     ) -> anyhow::Result<Texture> {
-        let data = load_binary(file_name)?;
+        let data = source.read(file_name)?;
         Texture::from_bytes(device, queue, &data, file_name)
     }
  
@@ -117,11 +310,29 @@ impl Texture {
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,
         name: &str
+    ) -> Arc<Texture> {
+        Self::create_depth_texture_ex(device,
+            config.width,
+            config.height,
+            1,
+            wgpu::TextureViewDimension::D2,
+            name,
+        )
+    }
+
+    // layers are used by the shadow cascades
+    pub fn create_depth_texture_ex(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        layers: u32,
+        view_dimension: wgpu::TextureViewDimension,
+        name: &str
     ) -> Arc<Texture> {
         let size = wgpu::Extent3d {
-            width: config.width,
-            height: config.height,
-            depth_or_array_layers: 1,
+            width,
+            height,
+            depth_or_array_layers: layers,
         };
  
         let texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -135,7 +346,10 @@ impl Texture {
             view_formats: &[],
         });
  
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(view_dimension),
+            ..Default::default()
+        });
  
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -156,12 +370,17 @@ impl Texture {
             texture,
             view,
             sampler,
+            view_dimension,
             name,
         };
 
         Arc::new(texture)
     }
 
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
     pub fn view(&self) -> &wgpu::TextureView {
         &self.view
     }
@@ -169,4 +388,8 @@ impl Texture {
     pub fn sampler(&self) -> &wgpu::Sampler {
         &self.sampler
     }
+
+    pub fn view_dimension(&self) -> wgpu::TextureViewDimension {
+        self.view_dimension
+    }
 }
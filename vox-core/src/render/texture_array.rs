@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use image::imageops::FilterType;
+use log::warn;
+
+use crate::{asset::AssetError, resources::asset_server::AssetServer, util::normalize_path, Texture};
+
+use super::mipmap;
+
+// Collects textures by file name, every unique file becomes one layer
+#[derive(Debug, Default)]
+pub struct TextureArrayBuilder {
+    layers: Vec<String>,
+    layer_indices: HashMap<String, u32>,
+}
+
+impl TextureArrayBuilder {
+    pub fn add(&mut self, file_name: &str) -> u32 {
+        let file_name = normalize_path(file_name);
+        if let Some(layer) = self.layer_indices.get(&file_name) {
+            return *layer;
+        }
+
+        let layer = self.layers.len() as u32;
+        self.layers.push(file_name.clone());
+        self.layer_indices.insert(file_name, layer);
+
+        layer
+    }
+
+    pub fn layer(&self, file_name: &str) -> Option<u32> {
+        self.layer_indices
+            .get(&normalize_path(file_name))
+            .copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    // layers are scaled to the size of the first one
+    pub fn build(&self,
+        name: &str,
+        asset_server: &AssetServer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Texture, AssetError> {
+        let load_error = |source: Box<dyn std::error::Error + Send + Sync>| AssetError::Load {
+            file_name: name.to_string(),
+            source,
+        };
+
+        let mut size = None;
+        let mut layers = Vec::with_capacity(self.layers.len());
+
+        for file_name in self.layers.iter() {
+            let bytes = asset_server.read(file_name)?;
+            let mut img = image::load_from_memory(&bytes)
+                .map_err(|e| load_error(e.into()))?
+                .to_rgba8();
+
+            let (width, height) = *size.get_or_insert(img.dimensions());
+            if img.dimensions() != (width, height) {
+                warn!("Scaling {} to {}x{} to fit {}", file_name, width, height, name);
+                img = image::imageops::resize(&img, width, height, FilterType::Nearest);
+            }
+
+            layers.push(mipmap::generate_mips(img, Texture::TEXTURE_FORMAT.is_srgb()));
+        }
+
+        Texture::from_layers(device, queue, &layers, name)
+            .map_err(|e| load_error(e.into()))
+    }
+}
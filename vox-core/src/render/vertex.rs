@@ -1,4 +1,5 @@
 use bytemuck::{Pod, Zeroable};
+use cgmath::{InnerSpace, Vector2, Vector3, Zero};
 
 pub type Index = u32;
 
@@ -8,6 +9,8 @@ pub struct Vertex {
     pub position: [f32; 3],
     pub tex_coords: [f32; 2],
     pub normal: [f32; 3],
+    // w is the handedness of the bitangent
+    pub tangent: [f32; 4],
 }
 
 impl Vertex {
@@ -34,7 +37,86 @@ impl Vertex {
                     offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
                     format: wgpu::VertexFormat::Float32x3,
                 },
+                // tangent
+                wgpu::VertexAttribute {
+                    shader_location: 3,
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
             ]
         }
     }
 }
+
+// averages the uv direction of every triangle a vertex is part of,
+// needed for normal mapping when the model does not provide tangents
+pub fn compute_tangents(vertices: &mut [Vertex], indices: &[Index]) {
+    let mut tangents = vec![Vector3::zero(); vertices.len()];
+    let mut bitangents = vec![Vector3::zero(); vertices.len()];
+
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0], triangle[1], triangle[2]]
+            .map(|idx| idx as usize);
+
+        if a >= vertices.len() || b >= vertices.len() || c >= vertices.len() {
+            continue;
+        }
+
+        let position = |idx: usize| Vector3::from(vertices[idx].position);
+        let tex_coords = |idx: usize| Vector2::from(vertices[idx].tex_coords);
+
+        let edge_1 = position(b) - position(a);
+        let edge_2 = position(c) - position(a);
+        let delta_uv_1 = tex_coords(b) - tex_coords(a);
+        let delta_uv_2 = tex_coords(c) - tex_coords(a);
+
+        let determinant = delta_uv_1.x * delta_uv_2.y - delta_uv_2.x * delta_uv_1.y;
+        if determinant.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let r = 1.0 / determinant;
+        let tangent = (edge_1 * delta_uv_2.y - edge_2 * delta_uv_1.y) * r;
+        let bitangent = (edge_2 * delta_uv_1.x - edge_1 * delta_uv_2.x) * r;
+
+        for idx in [a, b, c] {
+            tangents[idx] += tangent;
+            bitangents[idx] += bitangent;
+        }
+    }
+
+    for (idx, vertex) in vertices.iter_mut().enumerate() {
+        let normal = Vector3::from(vertex.normal);
+
+        // keep the tangent perpendicular to the normal
+        let tangent = tangents[idx] - normal * normal.dot(tangents[idx]);
+        let tangent = if tangent.magnitude2() > f32::EPSILON {
+            tangent.normalize()
+        } else {
+            any_perpendicular(normal)
+        };
+
+        let handedness = if normal.cross(tangent).dot(bitangents[idx]) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+
+        vertex.tangent = [tangent.x, tangent.y, tangent.z, handedness];
+    }
+}
+
+fn any_perpendicular(normal: Vector3<f32>) -> Vector3<f32> {
+    let axis = if normal.x.abs() < 0.9 {
+        Vector3::unit_x()
+    } else {
+        Vector3::unit_y()
+    };
+
+    let tangent = axis - normal * normal.dot(axis);
+    if tangent.magnitude2() > f32::EPSILON {
+        tangent.normalize()
+    } else {
+        axis
+    }
+}
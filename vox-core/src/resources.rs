@@ -9,3 +9,20 @@ pub mod game_state;
 pub mod render_server;
 pub mod egui_renderer;
 pub mod glyphon_renderer;
+pub mod voxel_atlas;
+pub mod world_time;
+pub mod sky_pipeline;
+pub mod fog;
+pub mod render_graph;
+pub mod post_process;
+pub mod post_process_pipeline;
+pub mod msaa;
+pub mod debug_view;
+pub mod debug_pipeline;
+pub mod gizmos;
+pub mod gizmo_pipeline;
+pub mod frame_stats;
+pub mod flythrough;
+pub mod time;
+#[cfg(not(target_arch="wasm32"))]
+pub mod hot_reload;
@@ -1,96 +1,214 @@
-use std::{any::{Any, TypeId}, collections::HashMap, hash::{DefaultHasher, Hash, Hasher}, sync::Arc};
+use std::{any::{type_name, Any, TypeId}, collections::HashMap, io, sync::Arc};
 
 use bevy_ecs::system::Resource;
+use log::{debug, info};
 
-use crate::{asset::Asset, util::get_extension, Model, Texture};
+use crate::{asset::{handle::Handle, source::{default_source, not_found, AssetSource}, Asset, AssetError, AssetLoader, ErasedAssetLoader, LoadContext}, render::{gltf_model::GltfLoader, model::ObjLoader, texture::TextureLoader}, util::{get_extension, normalize_path}};
 
-#[derive(Default, Resource)]
+#[derive(Resource)]
 pub struct AssetServer {
-    map: HashMap<(TypeId, u64), Arc<dyn Any + Send + Sync>>,
+    // assets by type and then by their normalized path
+    map: HashMap<TypeId, HashMap<Arc<str>, Arc<dyn Any + Send + Sync>>>,
+    loaders: HashMap<(TypeId, String), Arc<dyn ErasedAssetLoader>>,
+    // the last mounted source is searched first
+    sources: Vec<Box<dyn AssetSource>>,
+}
+
+impl Default for AssetServer {
+    fn default() -> Self {
+        Self::new(default_source())
+    }
 }
 
 impl AssetServer {
-    pub fn insert<T>(&mut self, asset: T)
-        -> Option<Arc<(dyn Any + Send + Sync + 'static)>>
+    pub fn new(source: Box<dyn AssetSource>) -> Self {
+        let map = HashMap::new();
+        let loaders = HashMap::new();
+        let sources = vec![source];
+
+        let mut asset_server = Self {
+            map,
+            loaders,
+            sources,
+        };
+
+        asset_server.register_loader(TextureLoader);
+        asset_server.register_loader(ObjLoader);
+        asset_server.register_loader(GltfLoader);
+
+        asset_server
+    }
+
+    // files in this source take priority over the ones already mounted
+    pub fn mount(&mut self, source: Box<dyn AssetSource>) {
+        info!("Mounted {}", source.describe());
+        self.sources.push(source);
+    }
+
+    pub fn read(&self, file_name: &str) -> Result<Vec<u8>, AssetError> {
+        let file_name = normalize_path(file_name);
+        let mut last_error = not_found(&file_name);
+
+        for source in self.sources.iter().rev() {
+            match source.read(&file_name) {
+                Ok(bytes) => return Ok(bytes),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => last_error = e,
+                Err(e) => {
+                    last_error = e;
+                    break;
+                },
+            }
+        }
+
+        Err(AssetError::Io {
+            file_name,
+            source: last_error,
+        })
+    }
+
+    pub fn register_loader<L: AssetLoader>(&mut self, loader: L) {
+        let type_id = TypeId::of::<L::Asset>();
+        let loader = Arc::new(loader);
+
+        for extension in loader.extensions() {
+            let extension = extension.to_lowercase();
+            let opt = self.loaders.insert((type_id, extension.clone()), loader.clone());
+            if opt.is_some() {
+                debug!("Replaced loader for .{} {}", extension, type_name::<L::Asset>());
+            }
+        }
+    }
+
+    pub fn insert<T>(&mut self, asset: T) -> Handle<T>
     where
         T: Asset + Send + Sync + 'static,
     {
-        let type_id = TypeId::of::<T>();
-        let file_name = asset.file_name();
+        let path: Arc<str> = normalize_path(asset.file_name()).into();
+        let asset = Arc::new(asset);
 
-        let hash = {
-            let mut hasher = DefaultHasher::new();
-            file_name.hash(&mut hasher);
-            hasher.finish()
-        };
+        self.map.entry(TypeId::of::<T>())
+            .or_default()
+            .insert(path.clone(), asset.clone());
 
-        self.map.insert((type_id, hash), Arc::new(asset))
+        Handle::new(path, asset)
     }
 
-    pub fn get<T>(&mut self, file_name: &str)
-        -> Option<Arc<T>>
+    pub fn get<T>(&self, file_name: &str)
+        -> Option<Handle<T>>
     where
         T: Asset + Send + Sync + 'static,
     {
-        let type_id = TypeId::of::<T>();
-        let hash = {
-            let mut hasher = DefaultHasher::new();
-            file_name.hash(&mut hasher);
-            hasher.finish()
-        };
+        let path = normalize_path(file_name);
 
-        self.map.get(&(type_id, hash))
-            .and_then(|arc| {
-                arc.clone()
+        self.map.get(&TypeId::of::<T>())
+            .and_then(|assets| assets.get_key_value(path.as_str()))
+            .and_then(|(path, any)| {
+                let asset = any.clone()
                     .downcast::<T>()
-                    .ok()
+                    .ok()?;
+
+                Some(Handle::new(path.clone(), asset))
             })
     }
 
-    // TODO: handles are now useless probably as we only need to return a texture id from the
-    // render server
     pub fn get_or_load<T>(&mut self, file_name: &str, device: &wgpu::Device, queue: &wgpu::Queue)
-        -> Option<Arc<T>>
+        -> Result<Handle<T>, AssetError>
     where
         T: Asset + Send + Sync + 'static,
     {
-        let type_id = TypeId::of::<T>();
-        let hash = {
-            let mut hasher = DefaultHasher::new();
-            file_name.hash(&mut hasher);
-            hasher.finish()
-        };
-
-        match self.map.get(&(type_id, hash)) {
-            Some(any) => any.clone().downcast().ok(),
-            None => {
-                self.load(file_name, device, queue);
-                self.get(file_name)
-            }
+        match self.get(file_name) {
+            Some(handle) => Ok(handle),
+            None => self.load(file_name, device, queue),
         }
     }
 
-    fn load(&mut self, file_name: &str, device: &wgpu::Device, queue: &wgpu::Queue) {
-        let extension = get_extension(file_name);
+    // loads the file again and replaces the stored asset,
+    // existing handles keep the old asset until they are dropped
+    pub fn reload<T>(&mut self, file_name: &str, device: &wgpu::Device, queue: &wgpu::Queue)
+        -> Result<Handle<T>, AssetError>
+    where
+        T: Asset + Send + Sync + 'static,
+    {
+        self.load(file_name, device, queue)
+    }
+
+    // drops every asset that is only referenced by the server,
+    // returns how many were unloaded
+    pub fn unload_unused(&mut self) -> usize {
+        let mut unloaded = 0;
+
+        // assets can hold handles to other assets (models to textures)
+        // so keep going until nothing else gets freed
+        loop {
+            let mut unloaded_pass = 0;
 
-        match extension {
-            Some("png") | Some("jpg") => self.load_texture(file_name, device, queue),
-            //Some("obj") => self.load_model(file_name, device, queue),
-            _ => {}
+            for assets in self.map.values_mut() {
+                assets.retain(|path, asset| {
+                    let is_used = Arc::strong_count(asset) > 1;
+                    if !is_used {
+                        debug!("Unloading unused asset {}", path);
+                        unloaded_pass += 1;
+                    }
+
+                    is_used
+                });
+            }
+
+            if unloaded_pass == 0 {
+                break;
+            }
+
+            unloaded += unloaded_pass;
         }
+
+        unloaded
     }
 
-    fn load_texture(&mut self, file_name: &str, device: &wgpu::Device, queue: &wgpu::Queue) {
-        let texture = Texture::load(file_name, device, queue)
-            .unwrap();
+    pub fn is_loaded<T: 'static>(&self, file_name: &str) -> bool {
+        let path = normalize_path(file_name);
 
-        self.insert(texture);
+        self.map.get(&TypeId::of::<T>())
+            .is_some_and(|assets| assets.contains_key(path.as_str()))
     }
 
-    //fn load_model(&mut self, file_name: &str, device: &wgpu::Device, queue: &wgpu::Queue) {
-    //    let model = Model::load(file_name, self, device, queue)
-    //        .unwrap();
-    //
-    //    self.insert(model);
-    //}
+    fn load<T>(&mut self, file_name: &str, device: &wgpu::Device, queue: &wgpu::Queue)
+        -> Result<Handle<T>, AssetError>
+    where
+        T: Asset + Send + Sync + 'static,
+    {
+        let file_name = normalize_path(file_name);
+        let extension = get_extension(&file_name)
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let no_loader = || AssetError::NoLoader {
+            file_name: file_name.clone(),
+            type_name: type_name::<T>(),
+        };
+
+        let loader = self.loaders
+            .get(&(TypeId::of::<T>(), extension))
+            .cloned()
+            .ok_or_else(no_loader)?;
+
+        let bytes = self.read(&file_name)?;
+
+        let mut ctx = LoadContext {
+            file_name: &file_name,
+            asset_server: self,
+            device,
+            queue,
+        };
+
+        let asset = loader.load_erased(&bytes, &mut ctx)?
+            .downcast::<T>()
+            .map_err(|_| no_loader())?;
+
+        let path: Arc<str> = file_name.as_str().into();
+        self.map.entry(TypeId::of::<T>())
+            .or_default()
+            .insert(path.clone(), asset.clone());
+
+        Ok(Handle::new(path, asset))
+    }
 }
@@ -0,0 +1,352 @@
+use bevy_ecs::prelude::*;
+use wgpu::{PipelineCompilationOptions, RenderPipelineDescriptor};
+
+use crate::{pass_ext::VoxDrawPassExt, render::{debug_view::{id_color, DebugUniform, DebugViewMode}, render_graph::SlotId, shader::{Shader, ShaderError}, vertex::Vertex}, InstanceRaw, Texture};
+
+use super::{debug_view::DebugView, default_pipeline::DefaultPipeline, frame_context::FrameContext, msaa::Msaa, render_context::RenderContext, render_server::RenderServer};
+
+// Draws DebugView over the finished scene, every draw
+// gets its own DebugUniform through a dynamic offset
+#[derive(Resource)]
+pub struct DebugPipeline {
+    uniform_buffer: wgpu::Buffer,
+    uniform_stride: u64,
+    uniform_capacity: usize,
+    bind_group: wgpu::BindGroup,
+    bind_group_layout: wgpu::BindGroupLayout,
+    fill_render_pipeline: wgpu::RenderPipeline,
+    // needs Features::POLYGON_MODE_LINE
+    line_render_pipeline: wgpu::RenderPipeline,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    sample_count: u32,
+    shader: Shader,
+}
+
+impl DebugPipeline {
+    pub const SHADER_FILE_NAME: &'static str = "debug.wgsl";
+    const WIREFRAME_COLOR: [f32; 3] = [0.9, 0.9, 0.9];
+    const OUTLINE_COLOR: [f32; 3] = [1.0, 0.8, 0.1];
+
+    pub fn new(device: &wgpu::Device,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        sample_count: u32,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Debug Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<DebugUniform>() as u64),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Debug Render Pipeline Layout"),
+            bind_group_layouts: &[
+                camera_bind_group_layout,
+                &bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let uniform_stride = (std::mem::size_of::<DebugUniform>() as u64).next_multiple_of(alignment);
+        let uniform_capacity = 64;
+        let (uniform_buffer, bind_group) = Self::create_uniforms(device,
+            &bind_group_layout,
+            uniform_stride,
+            uniform_capacity,
+        );
+
+        let shader = Shader::embedded(Self::SHADER_FILE_NAME, &[])
+            .unwrap();
+        let [fill_render_pipeline, line_render_pipeline] = Self::compile_pipelines(device,
+            &render_pipeline_layout,
+            &shader,
+            sample_count,
+        ).unwrap();
+
+        Self {
+            uniform_buffer,
+            uniform_stride,
+            uniform_capacity,
+            bind_group,
+            bind_group_layout,
+            fill_render_pipeline,
+            line_render_pipeline,
+            render_pipeline_layout,
+            sample_count,
+            shader,
+        }
+    }
+
+    // the pipelines in use are only replaced by set_shader
+    pub fn compile_shader(&self,
+        device: &wgpu::Device,
+        load: impl Fn(&str, &[&str]) -> Result<Shader, ShaderError>,
+    ) -> anyhow::Result<(Shader, [wgpu::RenderPipeline; 2])> {
+        let shader = load(Self::SHADER_FILE_NAME, &[])?;
+        let pipelines = Self::compile_pipelines(device,
+            &self.render_pipeline_layout,
+            &shader,
+            self.sample_count,
+        )?;
+
+        Ok((shader, pipelines))
+    }
+
+    pub fn set_shader(&mut self, (shader, pipelines): (Shader, [wgpu::RenderPipeline; 2])) {
+        [self.fill_render_pipeline, self.line_render_pipeline] = pipelines;
+        self.shader = shader;
+    }
+
+    // drawn over the model pass' targets, so it follows DefaultPipeline's
+    pub fn compile_sample_count(&self,
+        device: &wgpu::Device,
+        sample_count: u32,
+    ) -> anyhow::Result<[wgpu::RenderPipeline; 2]> {
+        Self::compile_pipelines(device,
+            &self.render_pipeline_layout,
+            &self.shader,
+            sample_count,
+        )
+    }
+
+    pub fn set_sample_count(&mut self,
+        pipelines: [wgpu::RenderPipeline; 2],
+        sample_count: u32,
+    ) {
+        [self.fill_render_pipeline, self.line_render_pipeline] = pipelines;
+        self.sample_count = sample_count;
+    }
+
+    fn compile_pipelines(device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &Shader,
+        sample_count: u32,
+    ) -> anyhow::Result<[wgpu::RenderPipeline; 2]> {
+        let fill_render_pipeline = DefaultPipeline::compile(device,
+            shader,
+            |module| Self::create_render_pipeline(device, layout, module, wgpu::PolygonMode::Fill, sample_count),
+        )?;
+
+        let line_render_pipeline = DefaultPipeline::compile(device,
+            shader,
+            |module| Self::create_render_pipeline(device, layout, module, wgpu::PolygonMode::Line, sample_count),
+        )?;
+
+        Ok([fill_render_pipeline, line_render_pipeline])
+    }
+
+    fn create_render_pipeline(device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        polygon_mode: wgpu::PolygonMode,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Debug Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    Vertex::desc(),
+                    InstanceRaw::desc(),
+                ],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: Texture::HDR_TEXTURE_FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            // tested against the scene's depth, pulled slightly
+            // towards the camera so it wins over the surfaces it covers
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_TEXTURE_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: -2,
+                    slope_scale: -1.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    fn create_uniforms(device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        stride: u64,
+        capacity: usize,
+    ) -> (wgpu::Buffer, wgpu::BindGroup) {
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Debug Buffer"),
+            size: stride * capacity as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Debug Bind Group"),
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &uniform_buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(std::mem::size_of::<DebugUniform>() as u64),
+                    }),
+                },
+            ],
+        });
+
+        (uniform_buffer, bind_group)
+    }
+
+    // grows the buffer when there are more draws than uniforms
+    fn write_uniforms(&mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        uniforms: &[DebugUniform],
+    ) {
+        if uniforms.len() > self.uniform_capacity {
+            self.uniform_capacity = uniforms.len().next_power_of_two();
+            (self.uniform_buffer, self.bind_group) = Self::create_uniforms(device,
+                &self.bind_group_layout,
+                self.uniform_stride,
+                self.uniform_capacity,
+            );
+        }
+
+        let mut data = vec![0u8; self.uniform_stride as usize * uniforms.len()];
+        for (uniform, chunk) in uniforms.iter().zip(data.chunks_mut(self.uniform_stride as usize)) {
+            chunk[..std::mem::size_of::<DebugUniform>()].copy_from_slice(bytemuck::bytes_of(uniform));
+        }
+
+        queue.write_buffer(&self.uniform_buffer, 0, &data);
+    }
+
+    fn uniform(mode: DebugViewMode, id: usize) -> DebugUniform {
+        let color = match mode {
+            DebugViewMode::Wireframe => Self::WIREFRAME_COLOR,
+            DebugViewMode::QuadOutlines => Self::OUTLINE_COLOR,
+            _ => id_color(id),
+        };
+
+        DebugUniform::new(mode, color)
+    }
+}
+
+// the render graph pass between the scene and post processing
+pub fn draw_debug_view(render_ctx: Res<RenderContext>,
+    mut frame_ctx: ResMut<FrameContext>,
+    mut debug_pipeline: ResMut<DebugPipeline>,
+    default_pipeline: Res<DefaultPipeline>,
+    render_server: Res<RenderServer>,
+    debug_view: Res<DebugView>,
+    msaa: Res<Msaa>,
+) {
+    let mode = debug_view.mode;
+    if mode == DebugViewMode::Off {
+        return;
+    }
+
+    // meshes and chunks get different tints even if their ids overlap
+    let meshes = render_server.meshes().iter()
+        .map(|mesh| DebugPipeline::uniform(mode, mesh.mesh_id() * 2));
+    let multi_indexed_meshes = render_server.multi_indexed_meshes().iter()
+        .map(|mesh| DebugPipeline::uniform(mode, mesh.mesh_id() * 2 + 1));
+    let uniforms = meshes.chain(multi_indexed_meshes)
+        .collect::<Vec<_>>();
+
+    debug_pipeline.write_uniforms(&render_ctx.device, &render_ctx.queue, &uniforms);
+
+    let mut encoder = render_ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Debug Encoder"),
+    });
+
+    let (view, resolve_target) = if msaa.applied_sample_count() > 1 {
+        (frame_ctx.view(SlotId::HDR_MULTISAMPLED), Some(frame_ctx.view(SlotId::HDR)))
+    } else {
+        (frame_ctx.view(SlotId::HDR), None)
+    };
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Debug Render Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: frame_ctx.view(SlotId::DEPTH),
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+
+    let render_pipeline = match mode {
+        DebugViewMode::Wireframe => &debug_pipeline.line_render_pipeline,
+        _ => &debug_pipeline.fill_render_pipeline,
+    };
+
+    render_pass.set_pipeline(render_pipeline);
+    render_pass.set_bind_group(0, default_pipeline.camera_bind_group(), &[]);
+
+    // in the same order as the uniforms
+    let stride = debug_pipeline.uniform_stride as u32;
+    let mut offsets = (0..).map(|draw_idx: u32| draw_idx * stride);
+
+    for mesh in render_server.meshes() {
+        render_pass.set_bind_group(1, &debug_pipeline.bind_group, &[offsets.next().unwrap()]);
+        render_pass.draw_mesh_geometry(mesh);
+    }
+
+    for multi_indexed_mesh in render_server.multi_indexed_meshes() {
+        render_pass.set_bind_group(1, &debug_pipeline.bind_group, &[offsets.next().unwrap()]);
+        render_pass.draw_mesh_multi_indexed_geometry(multi_indexed_mesh);
+    }
+
+    drop(render_pass);
+    frame_ctx.add_encoder(encoder);
+}
@@ -0,0 +1,25 @@
+use bevy_ecs::system::{Res, ResMut, Resource};
+
+use crate::render::debug_view::DebugViewMode;
+
+use super::input::InputRes;
+
+#[derive(Resource, Debug, Default)]
+pub struct DebugView {
+    pub mode: DebugViewMode,
+    // the key cycles once per press
+    was_key_pressed: bool,
+}
+
+// F3 steps through DebugViewMode
+pub fn cycle_debug_view(input_res: Res<InputRes>,
+    mut debug_view: ResMut<DebugView>,
+) {
+    let is_key_pressed = input_res.debug_view.is_pressed;
+    if is_key_pressed && !debug_view.was_key_pressed {
+        debug_view.mode = debug_view.mode.next();
+        log::info!("Debug view: {:?}", debug_view.mode);
+    }
+
+    debug_view.was_key_pressed = is_key_pressed;
+}
@@ -2,26 +2,59 @@ use bevy_ecs::prelude::*;
 use cgmath::{Matrix4, SquareMatrix};
 use wgpu::{util::DeviceExt, PipelineCompilationOptions, RenderPipelineDescriptor};
 
-use crate::{components::camerable::CameraUniform, render::vertex::Vertex, InstanceRaw, Texture};
+use crate::{components::camerable::{CameraComponent, CameraUniform}, render::{fog::{FogMode, FogUniform}, light::LightUniform, material::Material, shader::{Shader, ShaderError}, shadow_map::ShadowMap, vertex::Vertex}, InstanceRaw, Texture};
 
 #[derive(Resource)]
 pub struct DefaultPipeline {
     camera_buffer: wgpu::Buffer,
+    light_buffer: wgpu::Buffer,
+    fog_buffer: wgpu::Buffer,
+    // the camera, the light and the fog, shared by every draw
     camera_bind_group: wgpu::BindGroup,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
     render_pipeline: wgpu::RenderPipeline,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    // samples a texture array, used for chunks
+    voxel_render_pipeline: wgpu::RenderPipeline,
+    voxel_render_pipeline_layout: wgpu::PipelineLayout,
+    // depth only, renders into the shadow map cascades
+    shadow_render_pipeline: wgpu::RenderPipeline,
+    shadow_render_pipeline_layout: wgpu::PipelineLayout,
+    shadow_map: ShadowMap,
+    light: LightUniform,
+    // behind the sky, follows the horizon color
+    clear_color: wgpu::Color,
+    color_format: wgpu::TextureFormat,
+    sample_count: u32,
+    // kept to rebuild the pipelines when the sample count changes
+    shaders: [Shader; 3],
 }
 
 impl DefaultPipeline {
-    pub fn new(device: &wgpu::Device,
-        shader: &wgpu::ShaderModule,
-        config: &wgpu::SurfaceConfiguration,
-    ) -> Self {
+    pub const SHADER_FILE_NAME: &'static str = "shader.wgsl";
+    pub const SHADOW_SHADER_FILE_NAME: &'static str = "shadow.wgsl";
+    pub const VOXEL_DEFINES: &'static [&'static str] = &["TEXTURE_ARRAY"];
+
+    pub fn new(device: &wgpu::Device, sample_count: u32) -> Self {
         let camera_uniform: CameraUniform = Matrix4::identity()
             .into();
 
         let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Camera Buffer"),
-            contents: bytemuck::cast_slice(&camera_uniform),
+            contents: bytemuck::bytes_of(&camera_uniform),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let fog_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Fog Buffer"),
+            contents: bytemuck::bytes_of(&FogUniform::new(FogMode::Disabled, [0.0; 3])),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let light = LightUniform::default();
+        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Light Buffer"),
+            contents: bytemuck::bytes_of(&light),
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
@@ -30,14 +63,34 @@ impl DefaultPipeline {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
                         min_binding_size: None,
                     },
                     count: None,
-                }
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -49,43 +102,210 @@ impl DefaultPipeline {
                     binding: 0,
                     resource: camera_buffer.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: fog_buffer.as_entire_binding(),
+                },
             ],
         });
 
-        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("texture_bind_group_layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ]
+        let shadow_map = ShadowMap::new(device);
+
+        let render_pipeline_layout = Self::create_pipeline_layout(device,
+            &camera_bind_group_layout,
+            shadow_map.bind_group_layout(),
+            wgpu::TextureViewDimension::D2,
+        );
+
+        let voxel_render_pipeline_layout = Self::create_pipeline_layout(device,
+            &camera_bind_group_layout,
+            shadow_map.bind_group_layout(),
+            wgpu::TextureViewDimension::D2Array,
+        );
+
+        let shadow_render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Render Pipeline Layout"),
+            bind_group_layouts: &[
+                shadow_map.cascade_bind_group_layout(),
+            ],
+            push_constant_ranges: &[],
         });
 
-        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        let color_format = Texture::HDR_TEXTURE_FORMAT;
+
+        let shaders = Self::load_shaders(Shader::embedded)
+            .unwrap();
+        let [render_pipeline, voxel_render_pipeline, shadow_render_pipeline] = Self::compile_pipelines(device,
+            &render_pipeline_layout,
+            &voxel_render_pipeline_layout,
+            &shadow_render_pipeline_layout,
+            color_format,
+            sample_count,
+            &shaders,
+        ).unwrap();
+
+        Self {
+            render_pipeline,
+            render_pipeline_layout,
+            voxel_render_pipeline,
+            voxel_render_pipeline_layout,
+            shadow_render_pipeline,
+            shadow_render_pipeline_layout,
+            shadow_map,
+            light,
+            clear_color: wgpu::Color::BLACK,
+            color_format,
+            sample_count,
+            shaders,
+            camera_buffer,
+            light_buffer,
+            fog_buffer,
+            camera_bind_group,
+            camera_bind_group_layout,
+        }
+    }
+
+    // compiles every shader, the pipelines in use are only
+    // replaced by set_shaders, see hot_reload::reload_shader
+    pub fn compile_shaders(&self,
+        device: &wgpu::Device,
+        load: impl Fn(&str, &[&str]) -> Result<Shader, ShaderError>,
+    ) -> anyhow::Result<([Shader; 3], [wgpu::RenderPipeline; 3])> {
+        let shaders = Self::load_shaders(load)?;
+        let pipelines = Self::compile_pipelines(device,
+            &self.render_pipeline_layout,
+            &self.voxel_render_pipeline_layout,
+            &self.shadow_render_pipeline_layout,
+            self.color_format,
+            self.sample_count,
+            &shaders,
+        )?;
+
+        Ok((shaders, pipelines))
+    }
+
+    pub fn set_shaders(&mut self, (shaders, pipelines): ([Shader; 3], [wgpu::RenderPipeline; 3])) {
+        self.set_pipelines(pipelines);
+        self.shaders = shaders;
+    }
+
+    // the color and depth targets of the model pass must match,
+    // the pipelines in use are only replaced by set_sample_count
+    pub fn compile_sample_count(&self,
+        device: &wgpu::Device,
+        sample_count: u32,
+    ) -> anyhow::Result<[wgpu::RenderPipeline; 3]> {
+        Self::compile_pipelines(device,
+            &self.render_pipeline_layout,
+            &self.voxel_render_pipeline_layout,
+            &self.shadow_render_pipeline_layout,
+            self.color_format,
+            sample_count,
+            &self.shaders,
+        )
+    }
+
+    pub fn set_sample_count(&mut self,
+        pipelines: [wgpu::RenderPipeline; 3],
+        sample_count: u32,
+    ) {
+        self.set_pipelines(pipelines);
+        self.sample_count = sample_count;
+    }
+
+    fn set_pipelines(&mut self,
+        [render_pipeline, voxel_render_pipeline, shadow_render_pipeline]: [wgpu::RenderPipeline; 3],
+    ) {
+        self.render_pipeline = render_pipeline;
+        self.voxel_render_pipeline = voxel_render_pipeline;
+        self.shadow_render_pipeline = shadow_render_pipeline;
+    }
+
+    fn load_shaders(load: impl Fn(&str, &[&str]) -> Result<Shader, ShaderError>,
+    ) -> Result<[Shader; 3], ShaderError> {
+        Ok([
+            load(Self::SHADER_FILE_NAME, &[])?,
+            load(Self::SHADER_FILE_NAME, Self::VOXEL_DEFINES)?,
+            load(Self::SHADOW_SHADER_FILE_NAME, &[])?,
+        ])
+    }
+
+    fn compile_pipelines(device: &wgpu::Device,
+        render_pipeline_layout: &wgpu::PipelineLayout,
+        voxel_render_pipeline_layout: &wgpu::PipelineLayout,
+        shadow_render_pipeline_layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        [shader, voxel_shader, shadow_shader]: &[Shader; 3],
+    ) -> anyhow::Result<[wgpu::RenderPipeline; 3]> {
+        let render_pipeline = Self::compile(device,
+            shader,
+            |module| Self::create_render_pipeline(device, render_pipeline_layout, module, format, sample_count),
+        )?;
+
+        let voxel_render_pipeline = Self::compile(device,
+            voxel_shader,
+            |module| Self::create_render_pipeline(device, voxel_render_pipeline_layout, module, format, sample_count),
+        )?;
+
+        // the shadow map is never multisampled
+        let shadow_render_pipeline = Self::compile(device,
+            shadow_shader,
+            |module| Self::create_shadow_render_pipeline(device, shadow_render_pipeline_layout, module),
+        )?;
+
+        Ok([render_pipeline, voxel_render_pipeline, shadow_render_pipeline])
+    }
+
+    pub(crate) fn compile(device: &wgpu::Device,
+        shader: &Shader,
+        create_pipeline: impl FnOnce(&wgpu::ShaderModule) -> wgpu::RenderPipeline,
+    ) -> anyhow::Result<wgpu::RenderPipeline> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let module = device.create_shader_module(shader.descriptor());
+        let render_pipeline = create_pipeline(&module);
+
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            anyhow::bail!(shader.map_error(&error.to_string()));
+        }
+
+        Ok(render_pipeline)
+    }
+
+    fn create_pipeline_layout(device: &wgpu::Device,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        shadow_bind_group_layout: &wgpu::BindGroupLayout,
+        view_dimension: wgpu::TextureViewDimension,
+    ) -> wgpu::PipelineLayout {
+        let texture_bind_group_layout = Material::create_bind_group_layout(device,
+            view_dimension
+        );
+
+        device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
             bind_group_layouts: &[
                 &texture_bind_group_layout,
-                &camera_bind_group_layout,
+                camera_bind_group_layout,
+                shadow_bind_group_layout,
             ],
             push_constant_ranges: &[],
-        });
+        })
+    }
 
-        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+    fn create_render_pipeline(device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
                 module: shader,
                 entry_point: "vs_main",
@@ -99,7 +319,7 @@ impl DefaultPipeline {
                 module: shader,
                 entry_point: "fs_main",
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
+                    format,
                     blend: Some(wgpu::BlendState::REPLACE),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -123,6 +343,54 @@ impl DefaultPipeline {
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    fn create_shadow_render_pipeline(device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Shadow Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    Vertex::desc(),
+                    InstanceRaw::desc(),
+                ],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: None,
+            primitive : wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_TEXTURE_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                // slopes facing away from the sun need more bias against acne
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
             multisample: wgpu::MultisampleState {
                 count: 1,
                 mask: !0,
@@ -130,18 +398,27 @@ impl DefaultPipeline {
             },
             multiview: None,
             cache: None,
-        });
+        })
+    }
 
-        Self {
-            render_pipeline,
-            camera_buffer,
-            camera_bind_group,
-        }
+    pub fn shadow_pass<'a>(&self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        cascade: usize,
+    ) -> wgpu::RenderPass<'a> {
+        let mut render_pass = self.shadow_map
+            .cascade_pass(encoder, cascade);
+
+        render_pass.set_pipeline(&self.shadow_render_pipeline);
+        render_pass.set_bind_group(0, self.shadow_map.cascade_bind_group(cascade), &[]);
+
+        render_pass
     }
 
+    // with MSAA view is multisampled and resolves into resolve_target
     pub fn model_pass<'a>(&self,
         encoder: &'a mut wgpu::CommandEncoder,
         view: &wgpu::TextureView,
+        resolve_target: Option<&wgpu::TextureView>,
         depth_texture_view: &wgpu::TextureView,
     ) -> wgpu::RenderPass<'a> {
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -149,14 +426,9 @@ impl DefaultPipeline {
             // this is what @location(0) in the fragment shader targets
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                 view,
-                resolve_target: None,
+                resolve_target,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
-                        a: 1.0,
-                    }),
+                    load: wgpu::LoadOp::Clear(self.clear_color),
 
                     store: wgpu::StoreOp::Store,
                 },
@@ -174,14 +446,62 @@ impl DefaultPipeline {
         });
 
         render_pass.set_pipeline(&self.render_pipeline);
+        // stays bound when switching to the voxel pipeline
+        render_pass.set_bind_group(2, self.shadow_map.bind_group(), &[]);
 
         render_pass
     }
 
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    pub fn voxel_render_pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.voxel_render_pipeline
+    }
+
     pub fn camera_buffer(&self) -> &wgpu::Buffer {
         &self.camera_buffer
     }
 
+    pub fn set_light(&mut self, queue: &wgpu::Queue, light: LightUniform) {
+        queue.write_buffer(&self.light_buffer, 0, bytemuck::bytes_of(&light));
+        self.light = light;
+    }
+
+    pub fn light(&self) -> &LightUniform {
+        &self.light
+    }
+
+    pub fn write_fog(&self, queue: &wgpu::Queue, fog: &FogUniform) {
+        queue.write_buffer(&self.fog_buffer, 0, bytemuck::bytes_of(fog));
+    }
+
+    pub fn set_clear_color(&mut self, clear_color: wgpu::Color) {
+        self.clear_color = clear_color;
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.clear_color
+    }
+
+    // the cascades follow the camera, call after it moved
+    pub fn update_shadows(&self, queue: &wgpu::Queue, camera: &CameraComponent) {
+        self.shadow_map.update(queue, camera, self.light.direction.into());
+    }
+
+    pub fn shadow_map(&self) -> &ShadowMap {
+        &self.shadow_map
+    }
+
+    pub fn light_buffer(&self) -> &wgpu::Buffer {
+        &self.light_buffer
+    }
+
+    pub fn camera_bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.camera_bind_group_layout
+    }
+
     pub fn camera_bind_group(&self) -> &wgpu::BindGroup {
         &self.camera_bind_group
     }
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use bevy_ecs::system::Resource;
+use bevy_ecs::system::{Res, ResMut, Resource};
 use egui::Context;
 use egui_plot::PlotUi;
 use egui_wgpu::ScreenDescriptor;
@@ -8,29 +8,31 @@ use egui_winit::winit::event::WindowEvent;
 use wgpu::CommandEncoderDescriptor;
 use winit::window::Window;
 
-use crate::resources::{frame_context::FrameContext, game_state::GameState, render_context::RenderContext};
+use crate::{render::render_graph::SlotId, resources::{frame_context::FrameContext, game_state::GameState, render_context::RenderContext}};
 
 type ScreenCallback = dyn Fn(&Context, &mut GameState) + Send + Sync;
 
 #[derive(Resource)]
 pub struct EguiRenderer {
-    state: egui_winit::State,
+    context: Context,
+    // headless there is no window to take input from
+    state: Option<egui_winit::State>,
     renderer: egui_wgpu::Renderer,
     window_funcs: HashMap<GameState, Box<ScreenCallback>>,
 }
 
 impl EguiRenderer {
-    pub fn new(device: &wgpu::Device, window: &Window) -> Self {
+    pub fn new(device: &wgpu::Device, window: Option<&Window>) -> Self {
         let context = egui::Context::default();
         let viewport_id = context.viewport_id();
 
-        let state = egui_winit::State::new(context,
+        let state = window.map(|window| egui_winit::State::new(context.clone(),
             viewport_id,
             window,
             None,
             None,
             None
-        );
+        ));
 
         let renderer = egui_wgpu::Renderer::new(device,
             wgpu::TextureFormat::Bgra8UnormSrgb,
@@ -42,6 +44,7 @@ impl EguiRenderer {
         let window_funcs = HashMap::new();
 
         Self {
+            context,
             state,
             renderer,
             window_funcs,
@@ -49,7 +52,9 @@ impl EguiRenderer {
     }
 
     pub fn window_event(&mut self, window: &Window, event: &WindowEvent) {
-        let _ = self.state.on_window_event(window, event);
+        if let Some(state) = &mut self.state {
+            let _ = state.on_window_event(window, event);
+        }
     }
 
     pub fn add_window(&mut self,
@@ -60,6 +65,20 @@ impl EguiRenderer {
         self.window_funcs.insert(required_state, func);
     }
 
+    // called before the ui systems, so they can add windows to context
+    pub fn begin_frame(&mut self, window: Option<&Window>) {
+        let input = match (&mut self.state, window) {
+            (Some(state), Some(window)) => state.take_egui_input(window),
+            _ => egui::RawInput::default(),
+        };
+
+        self.context.begin_frame(input);
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
     pub fn draw(&mut self,
         render_ctx: &RenderContext,
         frame_ctx: &mut FrameContext,
@@ -68,19 +87,16 @@ impl EguiRenderer {
         let device = &render_ctx.device;
         let queue = &render_ctx.queue;
         let config = &render_ctx.config;
-        let window = &render_ctx.window;
+        let pixels_per_point = render_ctx.window.as_ref()
+            .map_or(1.0, |window| window.scale_factor() as f32);
 
-        let view = &frame_ctx.view;
+        let view = frame_ctx.view(SlotId::SURFACE);
         let mut encoder = render_ctx.device.create_command_encoder(&CommandEncoderDescriptor {
             label: Some("Egui Encoder"),
         });
 
-        let input = self.state.take_egui_input(window);
-        let context = self.state.egui_ctx();
-
-        // TODO: add egui_plot
+        let context = &self.context;
 
-        context.begin_frame(input);
         self.window_funcs
             .iter()
             .for_each(|(required_state, func)| {
@@ -92,10 +108,11 @@ impl EguiRenderer {
             });
         let output = context.end_frame();
 
-        self.state.handle_platform_output(window, output.platform_output);
+        if let (Some(state), Some(window)) = (&mut self.state, &render_ctx.window) {
+            state.handle_platform_output(window, output.platform_output);
+        }
 
-        let tris = self.state
-            .egui_ctx()
+        let tris = self.context
             .tessellate(output.shapes,
                 output.pixels_per_point
             );
@@ -109,7 +126,7 @@ impl EguiRenderer {
 
         let screen_descriptor = ScreenDescriptor {
             size_in_pixels: [config.width, config.height],
-            pixels_per_point: window.scale_factor() as f32,
+            pixels_per_point,
         };
 
         self.renderer
@@ -142,3 +159,12 @@ impl EguiRenderer {
         frame_ctx.add_encoder(encoder);
     }
 }
+
+// the render graph pass drawing the egui windows
+pub fn draw_egui(render_ctx: Res<RenderContext>,
+    mut frame_ctx: ResMut<FrameContext>,
+    mut egui_renderer: ResMut<EguiRenderer>,
+    mut state: ResMut<GameState>,
+) {
+    egui_renderer.draw(&render_ctx, &mut frame_ctx, &mut state);
+}
@@ -0,0 +1,204 @@
+use std::{path::PathBuf, time::Instant};
+
+use bevy_ecs::system::{Query, ResMut, Resource};
+use cgmath::{Point3, Vector3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{cli::FlythroughArgs, components::camerable::CameraComponent};
+
+// A closed Catmull-Rom spline the camera follows
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    points: Vec<Point3<f32>>,
+}
+
+impl CameraPath {
+    const POINT_COUNT: usize = 8;
+    // roughly the middle of the chunk GameScreen spawns
+    const CENTER: Point3<f32> = Point3::new(30.0, 20.0, 30.0);
+
+    // the same seed always gives the same path
+    pub fn from_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let step = std::f32::consts::TAU / Self::POINT_COUNT as f32;
+
+        let points = (0..Self::POINT_COUNT)
+            .map(|point_idx| {
+                let angle = point_idx as f32 * step + rng.gen_range(-0.3..0.3);
+                let radius = rng.gen_range(45.0..75.0);
+                let height = rng.gen_range(5.0..50.0);
+
+                Point3::new(Self::CENTER.x + radius * angle.cos(),
+                    height,
+                    Self::CENTER.z + radius * angle.sin(),
+                )
+            })
+            .collect();
+
+        Self {
+            points,
+        }
+    }
+
+    // t goes from 0 to 1 over the whole loop
+    pub fn position(&self, t: f32) -> Point3<f32> {
+        let point_count = self.points.len();
+        let t = t.rem_euclid(1.0) * point_count as f32;
+        let segment = t as usize;
+        let t = t.fract();
+
+        let point = |offset: usize| {
+            let point = self.points[(segment + point_count + offset - 1) % point_count];
+            Vector3::new(point.x, point.y, point.z)
+        };
+
+        let (p0, p1, p2, p3) = (point(0), point(1), point(2), point(3));
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        let position = 0.5 * ((2.0 * p1)
+            + (p2 - p0) * t
+            + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+            + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3);
+
+        Point3::new(position.x, position.y, position.z)
+    }
+
+    pub fn target(&self) -> Point3<f32> {
+        Self::CENTER
+    }
+}
+
+// Flies the camera along a CameraPath and times every frame,
+// the report is written once the last frame is done
+#[derive(Resource, Debug)]
+pub struct Flythrough {
+    args: FlythroughArgs,
+    path: CameraPath,
+    adapter_name: String,
+    frame_idx: u32,
+    // in milliseconds
+    frame_times: Vec<f32>,
+    last_frame: Option<Instant>,
+    is_finished: bool,
+}
+
+impl Flythrough {
+    // pipelines and buffers are still being created in these
+    const WARMUP_FRAMES: u32 = 30;
+
+    pub fn new(args: FlythroughArgs, adapter_name: String) -> Self {
+        let path = CameraPath::from_seed(args.seed);
+        let frame_times = Vec::with_capacity(args.frames as usize);
+
+        Self {
+            args,
+            path,
+            adapter_name,
+            frame_idx: 0,
+            frame_times,
+            last_frame: None,
+            is_finished: false,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.is_finished
+    }
+
+    pub fn output(&self) -> &PathBuf {
+        &self.args.output
+    }
+
+    // moves by frame instead of by time, so every run sees the same frames
+    fn advance(&mut self) -> Point3<f32> {
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            if self.frame_idx > Self::WARMUP_FRAMES {
+                self.frame_times.push((now - last_frame).as_secs_f32() * 1000.0);
+            }
+        }
+
+        self.last_frame = Some(now);
+        self.frame_idx += 1;
+
+        let timed_frame = self.frame_idx.saturating_sub(Self::WARMUP_FRAMES);
+        self.path.position(timed_frame as f32 / self.args.frames.max(1) as f32)
+    }
+
+    fn is_done(&self) -> bool {
+        self.frame_times.len() >= self.args.frames as usize
+    }
+
+    // nearest rank on the sorted frame times
+    fn percentile(sorted_frame_times: &[f32], percentile: f32) -> f32 {
+        let rank = (percentile / 100.0 * sorted_frame_times.len() as f32).ceil() as usize;
+        sorted_frame_times[rank.clamp(1, sorted_frame_times.len()) - 1]
+    }
+
+    // flat json, so the nightly job can parse it without knowing the engine
+    fn report(&self) -> String {
+        let mut frame_times = self.frame_times.clone();
+        frame_times.sort_by(f32::total_cmp);
+
+        let (min, max) = match (frame_times.first(), frame_times.last()) {
+            (Some(min), Some(max)) => (*min, *max),
+            _ => (0.0, 0.0),
+        };
+        let average = frame_times.iter().sum::<f32>() / frame_times.len().max(1) as f32;
+
+        // four decimals are plenty for milliseconds
+        let ms = |frame_time: f32| (frame_time as f64 * 1e4).round() / 1e4;
+
+        let mut report = serde_json::json!({
+            "seed": self.args.seed,
+            "frames": frame_times.len(),
+            "adapter": self.adapter_name,
+            "min_ms": ms(min),
+            "avg_ms": ms(average),
+            "max_ms": ms(max),
+        });
+
+        if !frame_times.is_empty() {
+            for percentile in [50, 90, 95, 99] {
+                let frame_time = Self::percentile(&frame_times, percentile as f32);
+                report[format!("p{}_ms", percentile)] = ms(frame_time).into();
+            }
+        }
+
+        let mut report = serde_json::to_string_pretty(&report)
+            .unwrap();
+        report.push('\n');
+        report
+    }
+}
+
+// the fixed updates run first, so the path wins over update_camera
+pub fn follow_flythrough(flythrough: Option<ResMut<Flythrough>>,
+    mut query: Query<&mut CameraComponent>,
+) {
+    let Some(mut flythrough) = flythrough else {
+        return;
+    };
+
+    if flythrough.is_finished {
+        return;
+    }
+
+    let position = flythrough.advance();
+    for mut camera_cmpnt in &mut query {
+        camera_cmpnt.snap_to(position, flythrough.path.target());
+    }
+
+    if !flythrough.is_done() {
+        return;
+    }
+
+    let report = flythrough.report();
+    match std::fs::write(flythrough.output(), &report) {
+        Ok(()) => log::info!("Wrote the flythrough report to {}", flythrough.output().display()),
+        Err(e) => log::error!("Could not write {}: {}", flythrough.output().display(), e),
+    }
+
+    flythrough.is_finished = true;
+}
@@ -0,0 +1,30 @@
+use bevy_ecs::system::Resource;
+
+use crate::render::fog::{FogMode, FogUniform};
+
+// Hides the far plane, where chunks appear and disappear
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct Fog {
+    pub mode: FogMode,
+    // None blends to the sky's horizon color
+    pub color: Option<[f32; 3]>,
+}
+
+impl Default for Fog {
+    // ends just before CameraComponent's zfar
+    fn default() -> Self {
+        Self {
+            mode: FogMode::Linear {
+                start: 60.0,
+                end: 95.0,
+            },
+            color: None,
+        }
+    }
+}
+
+impl Fog {
+    pub fn uniform(&self, sky_color: [f32; 3]) -> FogUniform {
+        FogUniform::new(self.mode, self.color.unwrap_or(sky_color))
+    }
+}
@@ -1,32 +1,59 @@
+use std::{collections::HashMap, sync::Arc};
+
 use bevy_ecs::system::Resource;
 
-use super::render_context::RenderContext;
+use crate::render::render_graph::SlotId;
+
+use super::render_context::{RenderContext, RenderTarget};
 
 #[derive(Resource)]
 pub struct FrameContext {
-    pub output: wgpu::SurfaceTexture,
-    pub view: wgpu::TextureView,
+    // None when rendering into a texture
+    pub output: Option<wgpu::SurfaceTexture>,
     pub encoders: Vec<wgpu::CommandEncoder>,
+    // the surface and the RenderGraph's transient textures
+    views: HashMap<SlotId, Arc<wgpu::TextureView>>,
 }
 
 impl FrameContext {
     pub fn new(render_ctx: &RenderContext,
         vec_capacity: Option<usize>
     ) -> Self {
-        let output = render_ctx.surface.get_current_texture().unwrap();
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let (output, view) = match &render_ctx.target {
+            RenderTarget::Surface(surface) => {
+                let output = surface.get_current_texture().unwrap();
+                let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+                (Some(output), view)
+            },
+            RenderTarget::Texture(texture) => {
+                (None, texture.create_view(&wgpu::TextureViewDescriptor::default()))
+            },
+        };
 
         let capacity = vec_capacity.unwrap_or(3);
         let encoders = Vec::with_capacity(capacity);
 
+        let mut views = HashMap::new();
+        views.insert(SlotId::SURFACE, Arc::new(view));
+
         Self {
             output,
-            view,
             encoders,
+            views,
         }
     }
 
     pub fn add_encoder(&mut self, encoder: wgpu::CommandEncoder) {
         self.encoders.push(encoder);
     }
+
+    pub fn insert_view(&mut self, slot: SlotId, view: Arc<wgpu::TextureView>) {
+        self.views.insert(slot, view);
+    }
+
+    // panics when no pass declared the slot this frame
+    pub fn view(&self, slot: SlotId) -> &wgpu::TextureView {
+        self.views.get(&slot)
+            .unwrap_or_else(|| panic!("No texture for slot {}", slot.0))
+    }
 }
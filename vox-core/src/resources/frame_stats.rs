@@ -0,0 +1,180 @@
+use std::{collections::{BTreeMap, VecDeque}, time::{Duration, Instant}};
+
+use bevy_ecs::system::{Res, Resource};
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+
+use super::{egui_renderer::EguiRenderer, render_server::RenderServer};
+
+// What the RenderServer holds, counted once per frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderCounts {
+    pub meshes: usize,
+    pub chunks: usize,
+    // one instance per greedy quad
+    pub quads: usize,
+    // for the model pass, the other passes draw about as many
+    pub draw_calls: usize,
+}
+
+impl RenderCounts {
+    pub fn new(render_server: &RenderServer) -> Self {
+        let meshes = render_server.meshes().iter()
+            .filter(|mesh| mesh.num_instances() > 0)
+            .count();
+        let multi_indexed_meshes = render_server.multi_indexed_meshes();
+
+        Self {
+            meshes,
+            chunks: multi_indexed_meshes.len(),
+            quads: multi_indexed_meshes.iter()
+                .map(|mesh| mesh.num_instances())
+                .sum(),
+            draw_calls: meshes + multi_indexed_meshes.iter()
+                .map(|mesh| mesh.draw_count() as usize)
+                .sum::<usize>(),
+        }
+    }
+}
+
+// Frame times and schedule timings in milliseconds, the newest last
+#[derive(Resource, Debug, Default)]
+pub struct FrameStats {
+    frame_times: VecDeque<f32>,
+    schedule_times: BTreeMap<&'static str, VecDeque<f32>>,
+    // summed until the next frame, FixedUpdate runs any number of times per frame
+    frame_schedule_times: BTreeMap<&'static str, Duration>,
+    render_counts: RenderCounts,
+    last_frame: Option<Instant>,
+}
+
+impl FrameStats {
+    pub const HISTORY_LEN: usize = 240;
+
+    // called by App::draw before anything else
+    pub fn begin_frame(&mut self, render_server: &RenderServer) {
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            let frame_time = (now - last_frame).as_secs_f32() * 1000.0;
+            Self::push(&mut self.frame_times, frame_time);
+        }
+
+        self.last_frame = Some(now);
+        self.render_counts = RenderCounts::new(render_server);
+    }
+
+    // called by the ScreenServer after running a schedule
+    pub fn record_schedule(&mut self, name: &'static str, elapsed: Duration) {
+        *self.frame_schedule_times.entry(name)
+            .or_default() += elapsed;
+    }
+
+    // called by App::draw once every schedule of the frame ran,
+    // one sample per schedule and frame, 0 when it did not run
+    pub fn end_schedules(&mut self) {
+        for name in self.frame_schedule_times.keys() {
+            self.schedule_times.entry(name)
+                .or_default();
+        }
+
+        for (name, times) in self.schedule_times.iter_mut() {
+            let elapsed = self.frame_schedule_times.remove(name)
+                .unwrap_or_default();
+            Self::push(times, elapsed.as_secs_f32() * 1000.0);
+        }
+    }
+
+    pub fn frame_times(&self) -> &VecDeque<f32> {
+        &self.frame_times
+    }
+
+    pub fn schedule_times(&self) -> &BTreeMap<&'static str, VecDeque<f32>> {
+        &self.schedule_times
+    }
+
+    pub fn render_counts(&self) -> RenderCounts {
+        self.render_counts
+    }
+
+    // averaged over the whole history, a single frame is too noisy
+    pub fn average_frame_time(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+
+        self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+    }
+
+    pub fn fps(&self) -> f32 {
+        let frame_time = self.average_frame_time();
+        if frame_time == 0.0 {
+            return 0.0;
+        }
+
+        1000.0 / frame_time
+    }
+
+    fn push(times: &mut VecDeque<f32>, time: f32) {
+        if times.len() == Self::HISTORY_LEN {
+            times.pop_front();
+        }
+
+        times.push_back(time);
+    }
+
+    fn plot_points(times: &VecDeque<f32>) -> PlotPoints {
+        times.iter()
+            .enumerate()
+            .map(|(frame_idx, time)| [frame_idx as f64, *time as f64])
+            .collect()
+    }
+}
+
+pub fn frame_stats_panel(egui_renderer: Res<EguiRenderer>,
+    frame_stats: Res<FrameStats>,
+) {
+    egui::Window::new("Frame Stats")
+        .default_open(false)
+        .show(egui_renderer.context(), |ui| {
+            let frame_time = frame_stats.average_frame_time();
+            ui.label(format!("{:.1} FPS ({:.2} ms)", frame_stats.fps(), frame_time));
+
+            Plot::new("frame_times")
+                .height(100.0)
+                .include_y(0.0)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .show(ui, |plot_ui| {
+                    plot_ui.line(Line::new(FrameStats::plot_points(frame_stats.frame_times()))
+                        .name("Frame"));
+                });
+
+            ui.separator();
+            ui.label("CPU time per schedule (ms)");
+            for (name, times) in frame_stats.schedule_times() {
+                let time = times.back().copied().unwrap_or_default();
+                ui.label(format!("{}: {:.3}", name, time));
+            }
+
+            Plot::new("schedule_times")
+                .height(100.0)
+                .include_y(0.0)
+                .legend(Legend::default())
+                .allow_drag(false)
+                .allow_zoom(false)
+                .allow_scroll(false)
+                .show(ui, |plot_ui| {
+                    for (name, times) in frame_stats.schedule_times() {
+                        plot_ui.line(Line::new(FrameStats::plot_points(times))
+                            .name(name));
+                    }
+                });
+
+            ui.separator();
+            let render_counts = frame_stats.render_counts();
+            ui.label(format!("Meshes: {}", render_counts.meshes));
+            ui.label(format!("Chunks: {}", render_counts.chunks));
+            ui.label(format!("Quads: {}", render_counts.quads));
+            ui.label(format!("Draw calls: {}", render_counts.draw_calls));
+        });
+}
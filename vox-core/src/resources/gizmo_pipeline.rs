@@ -0,0 +1,261 @@
+use bevy_ecs::prelude::*;
+use wgpu::{PipelineCompilationOptions, RenderPipelineDescriptor};
+
+use crate::{render::{gizmo::GizmoVertex, render_graph::SlotId, shader::{Shader, ShaderError}}, Texture};
+
+use super::{default_pipeline::DefaultPipeline, frame_context::FrameContext, gizmos::Gizmos, msaa::Msaa, render_context::RenderContext};
+
+// Draws the Gizmos as lines over the finished scene
+#[derive(Resource)]
+pub struct GizmoPipeline {
+    // the depth tested vertices first, then the overlay
+    vertex_buffer: wgpu::Buffer,
+    vertex_capacity: usize,
+    depth_tested_render_pipeline: wgpu::RenderPipeline,
+    overlay_render_pipeline: wgpu::RenderPipeline,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    sample_count: u32,
+    shader: Shader,
+}
+
+impl GizmoPipeline {
+    pub const SHADER_FILE_NAME: &'static str = "gizmo.wgsl";
+
+    pub fn new(device: &wgpu::Device,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        sample_count: u32,
+    ) -> Self {
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Gizmo Render Pipeline Layout"),
+            bind_group_layouts: &[
+                camera_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let vertex_capacity = 1024;
+        let vertex_buffer = Self::create_vertex_buffer(device, vertex_capacity);
+
+        let shader = Shader::embedded(Self::SHADER_FILE_NAME, &[])
+            .unwrap();
+        let [depth_tested_render_pipeline, overlay_render_pipeline] = Self::compile_pipelines(device,
+            &render_pipeline_layout,
+            &shader,
+            sample_count,
+        ).unwrap();
+
+        Self {
+            vertex_buffer,
+            vertex_capacity,
+            depth_tested_render_pipeline,
+            overlay_render_pipeline,
+            render_pipeline_layout,
+            sample_count,
+            shader,
+        }
+    }
+
+    // the pipelines in use are only replaced by set_shader
+    pub fn compile_shader(&self,
+        device: &wgpu::Device,
+        load: impl Fn(&str, &[&str]) -> Result<Shader, ShaderError>,
+    ) -> anyhow::Result<(Shader, [wgpu::RenderPipeline; 2])> {
+        let shader = load(Self::SHADER_FILE_NAME, &[])?;
+        let pipelines = Self::compile_pipelines(device,
+            &self.render_pipeline_layout,
+            &shader,
+            self.sample_count,
+        )?;
+
+        Ok((shader, pipelines))
+    }
+
+    pub fn set_shader(&mut self, (shader, pipelines): (Shader, [wgpu::RenderPipeline; 2])) {
+        [self.depth_tested_render_pipeline, self.overlay_render_pipeline] = pipelines;
+        self.shader = shader;
+    }
+
+    // drawn over the model pass' targets, so it follows DefaultPipeline's
+    pub fn compile_sample_count(&self,
+        device: &wgpu::Device,
+        sample_count: u32,
+    ) -> anyhow::Result<[wgpu::RenderPipeline; 2]> {
+        Self::compile_pipelines(device,
+            &self.render_pipeline_layout,
+            &self.shader,
+            sample_count,
+        )
+    }
+
+    pub fn set_sample_count(&mut self,
+        pipelines: [wgpu::RenderPipeline; 2],
+        sample_count: u32,
+    ) {
+        [self.depth_tested_render_pipeline, self.overlay_render_pipeline] = pipelines;
+        self.sample_count = sample_count;
+    }
+
+    fn compile_pipelines(device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &Shader,
+        sample_count: u32,
+    ) -> anyhow::Result<[wgpu::RenderPipeline; 2]> {
+        let depth_tested_render_pipeline = DefaultPipeline::compile(device,
+            shader,
+            |module| Self::create_render_pipeline(device, layout, module, wgpu::CompareFunction::LessEqual, sample_count),
+        )?;
+
+        let overlay_render_pipeline = DefaultPipeline::compile(device,
+            shader,
+            |module| Self::create_render_pipeline(device, layout, module, wgpu::CompareFunction::Always, sample_count),
+        )?;
+
+        Ok([depth_tested_render_pipeline, overlay_render_pipeline])
+    }
+
+    fn create_render_pipeline(device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        depth_compare: wgpu::CompareFunction,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Gizmo Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    GizmoVertex::desc(),
+                ],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: Texture::HDR_TEXTURE_FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            // lines never write depth so they do not hide each other
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_TEXTURE_FORMAT,
+                depth_write_enabled: false,
+                depth_compare,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    fn create_vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Gizmo Vertex Buffer"),
+            size: (std::mem::size_of::<GizmoVertex>() * capacity) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    // grows the buffer when there are more vertices than it holds
+    fn write_vertices(&mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        gizmos: &Gizmos,
+    ) {
+        let depth_tested = gizmos.depth_tested_vertices();
+        let overlay = gizmos.overlay_vertices();
+
+        let vertex_count = depth_tested.len() + overlay.len();
+        if vertex_count > self.vertex_capacity {
+            self.vertex_capacity = vertex_count.next_power_of_two();
+            self.vertex_buffer = Self::create_vertex_buffer(device, self.vertex_capacity);
+        }
+
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(depth_tested));
+        let overlay_offset = std::mem::size_of_val(depth_tested) as u64;
+        queue.write_buffer(&self.vertex_buffer, overlay_offset, bytemuck::cast_slice(overlay));
+    }
+}
+
+// the render graph pass between the scene and post processing,
+// AppState::draw clears the gizmos after every frame
+pub fn draw_gizmos(render_ctx: Res<RenderContext>,
+    mut frame_ctx: ResMut<FrameContext>,
+    mut gizmo_pipeline: ResMut<GizmoPipeline>,
+    gizmos: Res<Gizmos>,
+    default_pipeline: Res<DefaultPipeline>,
+    msaa: Res<Msaa>,
+) {
+    if gizmos.is_empty() {
+        return;
+    }
+
+    gizmo_pipeline.write_vertices(&render_ctx.device, &render_ctx.queue, &gizmos);
+
+    let mut encoder = render_ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Gizmo Encoder"),
+    });
+
+    let (view, resolve_target) = if msaa.applied_sample_count() > 1 {
+        (frame_ctx.view(SlotId::HDR_MULTISAMPLED), Some(frame_ctx.view(SlotId::HDR)))
+    } else {
+        (frame_ctx.view(SlotId::HDR), None)
+    };
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Gizmo Render Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: frame_ctx.view(SlotId::DEPTH),
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }),
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+
+    let depth_tested_count = gizmos.depth_tested_vertices().len() as u32;
+    let vertex_count = depth_tested_count + gizmos.overlay_vertices().len() as u32;
+
+    render_pass.set_bind_group(0, default_pipeline.camera_bind_group(), &[]);
+    render_pass.set_vertex_buffer(0, gizmo_pipeline.vertex_buffer.slice(..));
+
+    render_pass.set_pipeline(&gizmo_pipeline.depth_tested_render_pipeline);
+    render_pass.draw(0..depth_tested_count, 0..1);
+
+    render_pass.set_pipeline(&gizmo_pipeline.overlay_render_pipeline);
+    render_pass.draw(depth_tested_count..vertex_count, 0..1);
+
+    drop(render_pass);
+    frame_ctx.add_encoder(encoder);
+}
@@ -0,0 +1,146 @@
+use bevy_ecs::system::Resource;
+use cgmath::{Point3, Vector3};
+
+use crate::render::gizmo::GizmoVertex;
+
+// Debug shapes for the current frame, drawn and cleared by draw_gizmos
+#[derive(Resource, Debug)]
+pub struct Gizmos {
+    depth_tested: Vec<GizmoVertex>,
+    // drawn on top of everything
+    overlay: Vec<GizmoVertex>,
+    depth_test: bool,
+}
+
+impl Gizmos {
+    pub const RED: [f32; 3] = [1.0, 0.2, 0.2];
+    pub const GREEN: [f32; 3] = [0.2, 1.0, 0.2];
+    pub const BLUE: [f32; 3] = [0.2, 0.4, 1.0];
+    pub const YELLOW: [f32; 3] = [1.0, 0.9, 0.2];
+    pub const WHITE: [f32; 3] = [1.0, 1.0, 1.0];
+
+    const SPHERE_SEGMENTS: usize = 24;
+
+    // applies to the shapes pushed after it
+    pub fn set_depth_test(&mut self, depth_test: bool) {
+        self.depth_test = depth_test;
+    }
+
+    pub fn depth_test(&self) -> bool {
+        self.depth_test
+    }
+
+    pub fn line(&mut self,
+        start: Point3<f32>,
+        end: Point3<f32>,
+        color: [f32; 3],
+    ) {
+        let vertices = if self.depth_test {
+            &mut self.depth_tested
+        } else {
+            &mut self.overlay
+        };
+
+        vertices.push(GizmoVertex { position: start.into(), color });
+        vertices.push(GizmoVertex { position: end.into(), color });
+    }
+
+    // the direction's length is the ray's length
+    pub fn ray(&mut self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        color: [f32; 3],
+    ) {
+        self.line(origin, origin + direction, color);
+    }
+
+    pub fn aabb(&mut self,
+        min: Point3<f32>,
+        max: Point3<f32>,
+        color: [f32; 3],
+    ) {
+        let corner = |x: bool, y: bool, z: bool| Point3::new(
+            if x { max.x } else { min.x },
+            if y { max.y } else { min.y },
+            if z { max.z } else { min.z },
+        );
+
+        for a in [false, true] {
+            for b in [false, true] {
+                self.line(corner(false, a, b), corner(true, a, b), color);
+                self.line(corner(a, false, b), corner(a, true, b), color);
+                self.line(corner(a, b, false), corner(a, b, true), color);
+            }
+        }
+    }
+
+    // one circle around each axis
+    pub fn sphere(&mut self,
+        center: Point3<f32>,
+        radius: f32,
+        color: [f32; 3],
+    ) {
+        let step = std::f32::consts::TAU / Self::SPHERE_SEGMENTS as f32;
+        let axes = [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()];
+
+        for axis_idx in 0..3 {
+            let u = axes[(axis_idx + 1) % 3] * radius;
+            let v = axes[(axis_idx + 2) % 3] * radius;
+            let point = |segment: usize| {
+                let angle = segment as f32 * step;
+                center + u * angle.cos() + v * angle.sin()
+            };
+
+            for segment in 0..Self::SPHERE_SEGMENTS {
+                self.line(point(segment), point(segment + 1), color);
+            }
+        }
+    }
+
+    // x, y and z in red, green and blue
+    pub fn axes(&mut self, origin: Point3<f32>, length: f32) {
+        self.ray(origin, Vector3::unit_x() * length, Self::RED);
+        self.ray(origin, Vector3::unit_y() * length, Self::GREEN);
+        self.ray(origin, Vector3::unit_z() * length, Self::BLUE);
+    }
+
+    // a small cross, for points that have no extent
+    pub fn point(&mut self,
+        position: Point3<f32>,
+        size: f32,
+        color: [f32; 3],
+    ) {
+        let half = size * 0.5;
+        for axis in [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()] {
+            let offset = axis * half;
+            self.line(position - offset, position + offset, color);
+        }
+    }
+
+    pub fn depth_tested_vertices(&self) -> &[GizmoVertex] {
+        &self.depth_tested
+    }
+
+    pub fn overlay_vertices(&self) -> &[GizmoVertex] {
+        &self.overlay
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.depth_tested.is_empty() && self.overlay.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.depth_tested.clear();
+        self.overlay.clear();
+    }
+}
+
+impl Default for Gizmos {
+    fn default() -> Self {
+        Self {
+            depth_tested: Vec::new(),
+            overlay: Vec::new(),
+            depth_test: true,
+        }
+    }
+}
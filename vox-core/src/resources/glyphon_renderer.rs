@@ -1,9 +1,9 @@
 
-use bevy_ecs::{system::Resource};
+use bevy_ecs::system::{Res, ResMut, Resource};
 use glyphon::{Attrs, Buffer, Cache, Color, FontSystem, Metrics, Resolution, Shaping, SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer, Viewport};
 use wgpu::{CommandEncoderDescriptor, Device, MultisampleState, Queue};
 
-use crate::resources::{frame_context::FrameContext, render_context::RenderContext};
+use crate::{render::render_graph::SlotId, resources::{frame_context::FrameContext, render_context::RenderContext}};
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub struct LabelId(u32);
@@ -132,7 +132,7 @@ impl GlyphonRenderer {
         render_ctx: &RenderContext,
         frame_ctx: &mut FrameContext,
     ) {
-        let view = &frame_ctx.view;
+        let view = frame_ctx.view(SlotId::SURFACE);
         let mut encoder = render_ctx.device.create_command_encoder(&CommandEncoderDescriptor {
             label: Some("Glyphon Label Encoder"),
         });
@@ -194,3 +194,11 @@ impl GlyphonRenderer {
         render_pass
     }
 }
+
+// the render graph pass drawing the labels
+pub fn draw_glyphon_labels(render_ctx: Res<RenderContext>,
+    mut frame_ctx: ResMut<FrameContext>,
+    mut glyphon_renderer: ResMut<GlyphonRenderer>,
+) {
+    glyphon_renderer.draw(&render_ctx, &mut frame_ctx);
+}
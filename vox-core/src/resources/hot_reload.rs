@@ -0,0 +1,206 @@
+use std::{path::{Path, PathBuf}, sync::{mpsc::{self, Receiver}, Mutex}};
+
+use bevy_ecs::{system::Resource, world::{Mut, World}};
+use log::{error, info, warn};
+use notify::{RecursiveMode, Watcher};
+
+use crate::{render::shader::Shader, util::normalize_path, Texture};
+
+use super::{asset_server::AssetServer, debug_pipeline::DebugPipeline, default_pipeline::DefaultPipeline, gizmo_pipeline::GizmoPipeline, render_context::RenderContext, post_process_pipeline::PostProcessPipeline, render_server::RenderServer, sky_pipeline::SkyPipeline, voxel_atlas::VoxelAtlas};
+
+// debug builds read assets straight from res/, see default_source
+const RES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/res");
+const SHADER_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders");
+
+// Only inserted in debug builds, see AppState::new
+#[derive(Resource)]
+pub struct HotReload {
+    // dropping the watcher stops the events
+    _watcher: notify::RecommendedWatcher,
+    receiver: Mutex<Receiver<notify::Result<notify::Event>>>,
+}
+
+impl HotReload {
+    pub fn new() -> notify::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+
+        watcher.watch(Path::new(RES_DIR), RecursiveMode::Recursive)?;
+        watcher.watch(Path::new(SHADER_DIR), RecursiveMode::Recursive)?;
+
+        let receiver = Mutex::new(receiver);
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+        })
+    }
+
+    // every file written since the last call
+    fn changed_paths(&self) -> Vec<PathBuf> {
+        let receiver = self.receiver
+            .lock()
+            .unwrap();
+
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for event in receiver.try_iter() {
+            match event {
+                Ok(event) if event.kind.is_create() || event.kind.is_modify() => {
+                    for path in event.paths {
+                        if !paths.contains(&path) {
+                            paths.push(path);
+                        }
+                    }
+                },
+                Ok(_) => {},
+                Err(e) => warn!("Hot reload watcher error: {}", e),
+            }
+        }
+
+        paths
+    }
+}
+
+pub fn reload_changed(world: &mut World) {
+    let changed_paths = match world.get_resource::<HotReload>() {
+        Some(hot_reload) => hot_reload.changed_paths(),
+        None => return,
+    };
+
+    if changed_paths.is_empty() {
+        return;
+    }
+
+    world.resource_scope(|world: &mut World, render_ctx: Mut<RenderContext>| {
+        let device = &render_ctx.device;
+        let queue = &render_ctx.queue;
+        let mut reloaded_textures = false;
+
+        // any shader can be included by the default one
+        let is_shader_changed = changed_paths.iter()
+            .any(|path| path.starts_with(SHADER_DIR));
+
+        if is_shader_changed {
+            reload_shader(world, device);
+        }
+
+        let changed_files = changed_paths.iter()
+            .filter_map(|path| path.strip_prefix(RES_DIR).ok())
+            .map(|relative_path| normalize_path(&relative_path.to_string_lossy()))
+            .collect::<Vec<_>>();
+
+        for file_name in changed_files.iter() {
+            reloaded_textures |= reload_texture(world, device, queue, file_name);
+        }
+
+        reloaded_textures |= reload_voxel_atlas(world, device, queue, &changed_files);
+
+        if reloaded_textures {
+            world.resource_scope(|world: &mut World, asset_server: Mut<AssetServer>| {
+                let refreshed = world.resource_mut::<RenderServer>()
+                    .refresh_materials(&asset_server, device);
+
+                info!("Updated {} materials", refreshed);
+            });
+        }
+    });
+}
+
+fn reload_shader(world: &mut World, device: &wgpu::Device) {
+    let load = |file_name: &str, defines: &[&str]| {
+        Shader::preprocess(file_name, defines, |file_name| {
+            std::fs::read_to_string(Path::new(SHADER_DIR).join(file_name))
+        })
+    };
+
+    // everything is compiled before anything is swapped in,
+    // the pipelines share includes and must stay in sync
+    let compiled = (|| -> anyhow::Result<_> {
+        Ok((
+            world.resource::<DefaultPipeline>().compile_shaders(device, load)?,
+            world.resource::<SkyPipeline>().compile_shader(device, load)?,
+            world.resource::<PostProcessPipeline>().compile_shader(device, load)?,
+            world.resource::<DebugPipeline>().compile_shader(device, load)?,
+            world.resource::<GizmoPipeline>().compile_shader(device, load)?,
+        ))
+    })();
+
+    let (default, sky, post_process, debug, gizmo) = match compiled {
+        Ok(compiled) => compiled,
+        Err(e) => {
+            error!("Could not reload shaders, keeping the old pipelines: {}", e);
+            return;
+        },
+    };
+
+    world.resource_mut::<DefaultPipeline>().set_shaders(default);
+    world.resource_mut::<SkyPipeline>().set_shader(sky);
+    world.resource_mut::<PostProcessPipeline>().set_shader(post_process);
+    world.resource_mut::<DebugPipeline>().set_shader(debug);
+    world.resource_mut::<GizmoPipeline>().set_shader(gizmo);
+
+    info!("Reloaded shaders");
+}
+
+fn reload_texture(world: &mut World,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    file_name: &str,
+) -> bool {
+    let mut asset_server = world.resource_mut::<AssetServer>();
+    let mut results = Vec::new();
+
+    // textures nobody loaded yet will be read fresh anyway
+    if asset_server.is_loaded::<Texture>(file_name) {
+        results.push(asset_server.reload::<Texture>(file_name, device, queue));
+    }
+
+    // the same file can also be loaded as a normal map
+    if asset_server.is_loaded::<Texture>(&Texture::normal_map_name(file_name)) {
+        results.push(Texture::load_normal_map(&mut asset_server, file_name, device, queue));
+    }
+
+    let mut reloaded = false;
+    for result in results {
+        match result {
+            Ok(texture) => {
+                info!("Reloaded {}", texture.path());
+                reloaded = true;
+            },
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    reloaded
+}
+
+// the atlas reads its layers as raw bytes, they are never
+// loaded as textures of their own and reload_texture skips them
+fn reload_voxel_atlas(world: &mut World,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    changed_files: &[String],
+) -> bool {
+    let is_changed = world.get_resource::<VoxelAtlas>()
+        .is_some_and(|voxel_atlas| {
+            changed_files.iter().any(|file_name| voxel_atlas.contains_file(file_name))
+        });
+
+    if !is_changed {
+        return false;
+    }
+
+    world.resource_scope(|world: &mut World, mut voxel_atlas: Mut<VoxelAtlas>| {
+        let mut asset_server = world.resource_mut::<AssetServer>();
+        match voxel_atlas.rebuild(&mut asset_server, device, queue) {
+            Ok(()) => {
+                info!("Rebuilt {}", VoxelAtlas::FILE_NAME);
+                true
+            },
+            Err(e) => {
+                error!("{}", e);
+                false
+            },
+        }
+    })
+}
@@ -7,6 +7,7 @@ pub struct InputRes {
     pub right: KeyState,
     pub left: KeyState,
     pub backward: KeyState,
+    pub debug_view: KeyState,
 }
 
 #[derive(Default, Debug)]
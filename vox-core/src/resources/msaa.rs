@@ -0,0 +1,134 @@
+use bevy_ecs::{system::Resource, world::World};
+
+use crate::{render::render_graph::{SlotId, TransientTexture}, Texture};
+
+use super::{debug_pipeline::DebugPipeline, default_pipeline::DefaultPipeline, gizmo_pipeline::GizmoPipeline, render_context::RenderContext, render_graph::RenderGraph, sky_pipeline::SkyPipeline};
+
+// Multisampling for the model pass, changes are picked up
+// by apply_changes at the start of the next update
+#[derive(Resource, Debug)]
+pub struct Msaa {
+    sample_count: u32,
+    // what the pipelines and the render graph were last set up with
+    applied_sample_count: Option<u32>,
+    // both the HDR and the depth format support these
+    supported_sample_counts: Vec<u32>,
+}
+
+impl Msaa {
+    pub const SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+    // what every device supports for the HDR and the depth format
+    pub const GUARANTEED_SAMPLE_COUNTS: [u32; 2] = [1, 4];
+    pub const DEFAULT_SAMPLE_COUNT: u32 = 4;
+
+    // the adapter's format features only apply to devices
+    // created with TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+    pub fn new(adapter: &wgpu::Adapter, device: &wgpu::Device) -> Self {
+        let color_features = adapter.get_texture_format_features(Texture::HDR_TEXTURE_FORMAT);
+        let depth_features = adapter.get_texture_format_features(Texture::DEPTH_TEXTURE_FORMAT);
+
+        let is_adapter_specific = device.features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+
+        let supported_sample_counts = if is_adapter_specific {
+            Self::SAMPLE_COUNTS.into_iter()
+                .filter(|count| {
+                    color_features.flags.sample_count_supported(*count)
+                        && depth_features.flags.sample_count_supported(*count)
+                }).collect()
+        } else {
+            Self::GUARANTEED_SAMPLE_COUNTS.to_vec()
+        };
+
+        let mut msaa = Self {
+            sample_count: 1,
+            applied_sample_count: None,
+            supported_sample_counts,
+        };
+
+        msaa.set_sample_count(Self::DEFAULT_SAMPLE_COUNT);
+        msaa
+    }
+
+    // picks the highest supported count not above sample_count
+    pub fn set_sample_count(&mut self, sample_count: u32) -> u32 {
+        self.sample_count = self.supported_sample_counts.iter()
+            .copied()
+            .filter(|count| *count <= sample_count)
+            .max()
+            .unwrap_or(1);
+
+        self.sample_count
+    }
+
+    // what was asked for, apply_changes has not necessarily
+    // caught up yet, passes use applied_sample_count instead
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sample_count > 1
+    }
+
+    // what the pipelines and the render targets currently use
+    pub fn applied_sample_count(&self) -> u32 {
+        self.applied_sample_count.unwrap_or(1)
+    }
+
+    pub fn supported_sample_counts(&self) -> &[u32] {
+        &self.supported_sample_counts
+    }
+}
+
+// rebuilds the pipelines and the render targets when the
+// sample count changed, the render graph resizes them on its own
+pub fn apply_changes(world: &mut World) {
+    let msaa = world.resource::<Msaa>();
+    let sample_count = msaa.sample_count;
+    if msaa.applied_sample_count == Some(sample_count) {
+        return;
+    }
+
+    // everything is compiled before anything is swapped in, the
+    // model pass breaks if its pipelines disagree on the count
+    let device = &world.resource::<RenderContext>().device;
+    let compiled = (|| -> anyhow::Result<_> {
+        Ok((
+            world.resource::<DefaultPipeline>().compile_sample_count(device, sample_count)?,
+            world.resource::<SkyPipeline>().compile_sample_count(device, sample_count)?,
+            world.resource::<DebugPipeline>().compile_sample_count(device, sample_count)?,
+            world.resource::<GizmoPipeline>().compile_sample_count(device, sample_count)?,
+        ))
+    })();
+
+    // nothing changed, the old count stays applied
+    let (default, sky, debug, gizmo) = match compiled {
+        Ok(compiled) => compiled,
+        Err(e) => {
+            log::error!("Could not switch to {}x MSAA: {}", sample_count, e);
+            let mut msaa = world.resource_mut::<Msaa>();
+            msaa.sample_count = msaa.applied_sample_count();
+            return;
+        },
+    };
+
+    world.resource_mut::<DefaultPipeline>().set_sample_count(default, sample_count);
+    world.resource_mut::<SkyPipeline>().set_sample_count(sky, sample_count);
+    world.resource_mut::<DebugPipeline>().set_sample_count(debug, sample_count);
+    world.resource_mut::<GizmoPipeline>().set_sample_count(gizmo, sample_count);
+
+    world.resource_mut::<Msaa>()
+        .applied_sample_count = Some(sample_count);
+
+    let mut render_graph = world.resource_mut::<RenderGraph>();
+    render_graph.add_texture(SlotId::DEPTH, TransientTexture::depth()
+        .with_sample_count(sample_count));
+
+    if sample_count > 1 {
+        render_graph.add_texture(SlotId::HDR_MULTISAMPLED, TransientTexture::color(Texture::HDR_TEXTURE_FORMAT)
+            .with_sample_count(sample_count));
+    } else {
+        render_graph.remove_texture(SlotId::HDR_MULTISAMPLED);
+    }
+}
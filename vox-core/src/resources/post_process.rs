@@ -0,0 +1,100 @@
+use bevy_ecs::system::{Res, ResMut, Resource};
+
+use crate::render::post_process::{PostEffect, PostProcessUniform};
+
+use super::{egui_renderer::EguiRenderer, msaa::Msaa};
+
+#[derive(Resource, Debug, Clone)]
+pub struct PostProcessSettings {
+    enabled_effects: [bool; PostEffect::ALL.len()],
+    pub exposure: f32,
+    pub gamma: f32,
+    pub lut_strength: f32,
+}
+
+impl Default for PostProcessSettings {
+    // sRGB surfaces encode gamma on their own,
+    // see PostProcessSettings::for_surface
+    fn default() -> Self {
+        let mut enabled_effects = [true; PostEffect::ALL.len()];
+        enabled_effects[PostEffect::Gamma.index()] = false;
+
+        Self {
+            enabled_effects,
+            exposure: 0.0,
+            gamma: 2.2,
+            lut_strength: 1.0,
+        }
+    }
+}
+
+impl PostProcessSettings {
+    pub fn for_surface(format: wgpu::TextureFormat) -> Self {
+        let mut settings = Self::default();
+        settings.set_enabled(PostEffect::Gamma, !format.is_srgb());
+        settings
+    }
+
+    pub fn is_enabled(&self, effect: PostEffect) -> bool {
+        self.enabled_effects[effect.index()]
+    }
+
+    pub fn set_enabled(&mut self, effect: PostEffect, is_enabled: bool) {
+        self.enabled_effects[effect.index()] = is_enabled;
+    }
+
+    pub fn enabled_effects(&self) -> impl Iterator<Item = PostEffect> + '_ {
+        PostEffect::ALL.into_iter()
+            .filter(|effect| self.is_enabled(*effect))
+    }
+
+    pub fn uniform(&self, lut_size: u32, width: u32, height: u32) -> PostProcessUniform {
+        PostProcessUniform {
+            exposure: self.exposure,
+            gamma: self.gamma,
+            lut_strength: self.lut_strength,
+            lut_size: lut_size as f32,
+            texel_size: [1.0 / width as f32, 1.0 / height as f32],
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+pub fn post_process_panel(egui_renderer: Res<EguiRenderer>,
+    mut settings: ResMut<PostProcessSettings>,
+    mut msaa: ResMut<Msaa>,
+) {
+    egui::Window::new("Post Processing")
+        .default_open(false)
+        .resizable(false)
+        .show(egui_renderer.context(), |ui| {
+            for effect in PostEffect::ALL {
+                let mut is_enabled = settings.is_enabled(effect);
+                if ui.checkbox(&mut is_enabled, effect.name()).changed() {
+                    settings.set_enabled(effect, is_enabled);
+                }
+            }
+
+            // not a post effect, but it sits next to FXAA
+            let mut sample_count = msaa.sample_count();
+            egui::ComboBox::from_label("MSAA")
+                .selected_text(format!("{}x", sample_count))
+                .show_ui(ui, |ui| {
+                    for count in msaa.supported_sample_counts() {
+                        ui.selectable_value(&mut sample_count, *count, format!("{}x", count));
+                    }
+                });
+
+            if sample_count != msaa.sample_count() {
+                msaa.set_sample_count(sample_count);
+            }
+
+            ui.separator();
+            ui.add(egui::Slider::new(&mut settings.exposure, -4.0..=4.0)
+                .text("Exposure"));
+            ui.add(egui::Slider::new(&mut settings.gamma, 1.0..=3.0)
+                .text("Gamma"));
+            ui.add(egui::Slider::new(&mut settings.lut_strength, 0.0..=1.0)
+                .text("LUT Strength"));
+        });
+}
@@ -0,0 +1,382 @@
+use bevy_ecs::prelude::*;
+use bytemuck::Zeroable;
+use wgpu::{util::DeviceExt, PipelineCompilationOptions, RenderPipelineDescriptor};
+
+use crate::{render::{post_process::{PostEffect, PostProcessUniform}, render_graph::SlotId, shader::{Shader, ShaderError}}, Texture};
+
+use super::{asset_server::AssetServer, default_pipeline::DefaultPipeline, frame_context::FrameContext, post_process::PostProcessSettings, render_context::RenderContext};
+
+// Runs the enabled PostEffects from the HDR scene to the surface,
+// every effect but the last writes to one of two transient textures
+#[derive(Resource)]
+pub struct PostProcessPipeline {
+    uniform_buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    // indexed by PostEffect::index, the blit is last. The first of each
+    // pair writes to the transient textures, the second to the surface
+    render_pipelines: Vec<[wgpu::RenderPipeline; 2]>,
+    input_sampler: wgpu::Sampler,
+    lut_view: wgpu::TextureView,
+    lut_sampler: wgpu::Sampler,
+    lut_size: u32,
+    surface_format: wgpu::TextureFormat,
+}
+
+impl PostProcessPipeline {
+    pub const SHADER_FILE_NAME: &'static str = "post_process.wgsl";
+    pub const PING: SlotId = SlotId("post_process_ping");
+    pub const PONG: SlotId = SlotId("post_process_pong");
+    // copies the scene when every effect is disabled
+    const BLIT: usize = PostEffect::ALL.len();
+    const IDENTITY_LUT_SIZE: u32 = 16;
+
+    pub fn new(device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> Self {
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Post Process Buffer"),
+            contents: bytemuck::bytes_of(&PostProcessUniform::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Process Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Process Render Pipeline Layout"),
+            bind_group_layouts: &[
+                &bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let linear_sampler = || {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            })
+        };
+
+        let input_sampler = linear_sampler();
+        let lut_sampler = linear_sampler();
+
+        let lut_size = Self::IDENTITY_LUT_SIZE;
+        let lut_view = Self::create_lut(device, queue, lut_size, &Self::identity_lut(lut_size));
+
+        let surface_format = config.format;
+        let shader = Shader::embedded(Self::SHADER_FILE_NAME, &[])
+            .unwrap();
+        let render_pipelines = Self::compile_pipelines(device,
+            &render_pipeline_layout,
+            &shader,
+            surface_format,
+        ).unwrap();
+
+        Self {
+            uniform_buffer,
+            bind_group_layout,
+            render_pipeline_layout,
+            render_pipelines,
+            input_sampler,
+            lut_view,
+            lut_sampler,
+            lut_size,
+            surface_format,
+        }
+    }
+
+    // the pipelines in use are only replaced by set_shader
+    pub fn compile_shader(&self,
+        device: &wgpu::Device,
+        load: impl Fn(&str, &[&str]) -> Result<Shader, ShaderError>,
+    ) -> anyhow::Result<Vec<[wgpu::RenderPipeline; 2]>> {
+        let shader = load(Self::SHADER_FILE_NAME, &[])?;
+        Self::compile_pipelines(device,
+            &self.render_pipeline_layout,
+            &shader,
+            self.surface_format,
+        )
+    }
+
+    pub fn set_shader(&mut self, render_pipelines: Vec<[wgpu::RenderPipeline; 2]>) {
+        self.render_pipelines = render_pipelines;
+    }
+
+    fn compile_pipelines(device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &Shader,
+        surface_format: wgpu::TextureFormat,
+    ) -> anyhow::Result<Vec<[wgpu::RenderPipeline; 2]>> {
+        let entry_points = PostEffect::ALL.iter()
+            .map(PostEffect::entry_point)
+            .chain(["fs_blit"]);
+
+        let mut render_pipelines = Vec::with_capacity(Self::BLIT + 1);
+        for entry_point in entry_points {
+            let [intermediate, surface] = [Texture::HDR_TEXTURE_FORMAT, surface_format].map(|format| {
+                DefaultPipeline::compile(device,
+                    shader,
+                    |module| Self::create_render_pipeline(device, layout, module, entry_point, format),
+                )
+            });
+
+            render_pipelines.push([intermediate?, surface?]);
+        }
+
+        Ok(render_pipelines)
+    }
+
+    fn create_render_pipeline(device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        entry_point: &str,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    // texels are laid out red first, then green, then blue
+    fn identity_lut(size: u32) -> Vec<u8> {
+        let level = |value: u32| (value * 255 / (size - 1)) as u8;
+
+        (0..size).flat_map(|blue| {
+            (0..size).flat_map(move |green| {
+                (0..size).flat_map(move |red| [level(red), level(green), level(blue), 255])
+            })
+        }).collect()
+    }
+
+    fn create_lut(device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: u32,
+        data: &[u8],
+    ) -> wgpu::TextureView {
+        let texture = device.create_texture_with_data(queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Color Grading LUT"),
+                size: wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: size,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D3,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            data,
+        );
+
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    // expects the usual strip layout, size * size wide and size high
+    // with one blue slice after the other
+    pub fn set_lut(&mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img: &image::RgbaImage,
+    ) -> anyhow::Result<()> {
+        let size = img.height();
+        if size < 2 || img.width() != size * size {
+            anyhow::bail!("A {}x{} image is not a LUT strip", img.width(), img.height());
+        }
+
+        let data = (0..size).flat_map(|blue| {
+            (0..size).flat_map(move |green| {
+                (0..size).flat_map(move |red| img.get_pixel(blue * size + red, green).0)
+            })
+        }).collect::<Vec<u8>>();
+
+        self.lut_view = Self::create_lut(device, queue, size, &data);
+        self.lut_size = size;
+
+        Ok(())
+    }
+
+    pub fn load_lut(&mut self,
+        asset_server: &AssetServer,
+        file_name: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> anyhow::Result<()> {
+        let bytes = asset_server.read(file_name)?;
+        let img = image::load_from_memory(&bytes)?;
+
+        self.set_lut(device, queue, &img.to_rgba8())
+    }
+
+    pub fn draw(&self,
+        render_ctx: &RenderContext,
+        frame_ctx: &FrameContext,
+        settings: &PostProcessSettings,
+    ) -> wgpu::CommandEncoder {
+        let uniform = settings.uniform(self.lut_size,
+            render_ctx.config.width,
+            render_ctx.config.height,
+        );
+        render_ctx.queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+
+        let mut stages = settings.enabled_effects()
+            .map(|effect| effect.index())
+            .collect::<Vec<_>>();
+
+        if stages.is_empty() {
+            stages.push(Self::BLIT);
+        }
+
+        let mut encoder = render_ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Post Process Encoder"),
+        });
+
+        let mut input = SlotId::HDR;
+        for (stage_idx, pipeline_idx) in stages.iter().enumerate() {
+            let is_last = stage_idx == stages.len() - 1;
+            let output = match (is_last, stage_idx % 2) {
+                (true, _) => SlotId::SURFACE,
+                (false, 0) => Self::PING,
+                (false, _) => Self::PONG,
+            };
+
+            let bind_group = render_ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Post Process Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(frame_ctx.view(input)),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.input_sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: self.uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::TextureView(&self.lut_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: wgpu::BindingResource::Sampler(&self.lut_sampler),
+                    },
+                ],
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Post Process Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: frame_ctx.view(output),
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.render_pipelines[*pipeline_idx][is_last as usize]);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            // one triangle covering the screen
+            render_pass.draw(0..3, 0..1);
+
+            input = output;
+        }
+
+        encoder
+    }
+}
+
+// the render graph pass between the scene and the overlays
+pub fn draw_post_process(render_ctx: Res<RenderContext>,
+    mut frame_ctx: ResMut<FrameContext>,
+    pipeline: Res<PostProcessPipeline>,
+    settings: Res<PostProcessSettings>,
+) {
+    let encoder = pipeline.draw(&render_ctx, &frame_ctx, &settings);
+    frame_ctx.add_encoder(encoder);
+}
@@ -3,14 +3,20 @@ use std::sync::Arc;
 use bevy_ecs::system::Resource;
 use winit::window::Window;
 
-use crate::Texture;
+// Where the frames end up
+pub enum RenderTarget {
+    Surface(wgpu::Surface<'static>),
+    // headless, see HeadlessApp::capture
+    Texture(wgpu::Texture),
+}
 
 #[derive(Resource)]
 pub struct RenderContext {
-    pub window: Arc<Window>,
-    pub depth_texture: Arc<Texture>,
+    // None when headless
+    pub window: Option<Arc<Window>>,
     pub device: wgpu::Device,
-    pub surface: wgpu::Surface<'static>,
+    pub target: RenderTarget,
+    // also describes the texture when headless
     pub config: wgpu::SurfaceConfiguration,
     pub queue: wgpu::Queue,
     pub size: winit::dpi::PhysicalSize<u32>,
@@ -0,0 +1,267 @@
+use std::{collections::{BTreeSet, HashMap}, sync::Arc};
+
+use bevy_ecs::{system::Resource, world::World};
+
+use crate::{render::render_graph::{RenderGraphError, RenderGraphPass, SlotAccess, SlotId, TransientTexture}, world_ext::WorldExt, Texture};
+
+use super::{frame_context::FrameContext, render_context::RenderContext};
+
+#[derive(Debug)]
+struct AllocatedTexture {
+    descriptor: TransientTexture,
+    width: u32,
+    height: u32,
+    view: Arc<wgpu::TextureView>,
+}
+
+// Runs every pass of the frame in dependency order, passes only
+// declare the slots they touch so new ones can be added from anywhere
+#[derive(Resource)]
+pub struct RenderGraph {
+    passes: Vec<RenderGraphPass>,
+    transient_textures: HashMap<SlotId, TransientTexture>,
+    allocated_textures: HashMap<SlotId, AllocatedTexture>,
+}
+
+impl Default for RenderGraph {
+    fn default() -> Self {
+        let mut render_graph = Self {
+            passes: Vec::new(),
+            transient_textures: HashMap::new(),
+            allocated_textures: HashMap::new(),
+        };
+
+        render_graph.add_texture(SlotId::DEPTH, TransientTexture::depth());
+        render_graph.add_texture(SlotId::HDR, TransientTexture::color(Texture::HDR_TEXTURE_FORMAT));
+        render_graph
+    }
+}
+
+impl RenderGraph {
+    pub fn add_pass(&mut self, pass: RenderGraphPass) -> Result<(), RenderGraphError> {
+        if self.passes.iter().any(|added| added.name == pass.name) {
+            return Err(RenderGraphError::DuplicatePass(pass.name));
+        }
+
+        self.passes.push(pass);
+        Ok(())
+    }
+
+    // slots without a transient texture, like the surface or
+    // the shadow map, are only used to order the passes
+    pub fn add_texture(&mut self, slot: SlotId, texture: TransientTexture) {
+        self.transient_textures.insert(slot, texture);
+    }
+
+    pub fn remove_texture(&mut self, slot: SlotId) {
+        self.transient_textures.remove(&slot);
+    }
+
+    pub fn passes(&self) -> impl Iterator<Item = &RenderGraphPass> {
+        self.passes.iter()
+    }
+
+    // expects a FrameContext holding the surface
+    pub fn execute(&mut self, world: &mut World) -> Result<(), RenderGraphError> {
+        let state = world.game_state();
+        let order = self.sort(|pass| {
+            pass.game_state.map_or(true, |game_state| game_state == state)
+        })?;
+
+        self.allocate_textures(world, &order);
+
+        for idx in order {
+            let pass = &mut self.passes[idx];
+            if !pass.is_initialized {
+                pass.system.initialize(world);
+                pass.is_initialized = true;
+            }
+
+            pass.system.run((), world);
+            pass.system.apply_deferred(world);
+        }
+
+        Ok(())
+    }
+
+    // writers come first, then modifiers in the order they were
+    // added, then readers
+    fn sort(&self,
+        is_active: impl Fn(&RenderGraphPass) -> bool,
+    ) -> Result<Vec<usize>, RenderGraphError> {
+        let mut active = (0..self.passes.len())
+            .filter(|idx| is_active(&self.passes[*idx]))
+            .collect::<Vec<_>>();
+
+        // passes reading a slot nothing draws to this frame are skipped,
+        // like post processing on a screen without a scene
+        loop {
+            let produced_slots = active.iter()
+                .flat_map(|idx| self.passes[*idx].slots.iter())
+                .filter(|(_, access)| *access != SlotAccess::Read)
+                .map(|(slot, _)| *slot)
+                .collect::<BTreeSet<_>>();
+
+            let active_count = active.len();
+            active.retain(|idx| {
+                self.passes[*idx].slots.iter()
+                    .all(|(slot, access)| *access != SlotAccess::Read || produced_slots.contains(slot))
+            });
+
+            if active.len() == active_count {
+                break;
+            }
+        }
+
+        let mut slot_users: HashMap<SlotId, [Vec<usize>; 3]> = HashMap::new();
+        for idx in active.iter().copied() {
+            for (slot, access) in self.passes[idx].slots.iter() {
+                let users = slot_users.entry(*slot)
+                    .or_default();
+
+                let users = match access {
+                    SlotAccess::Write => &mut users[0],
+                    SlotAccess::Modify => &mut users[1],
+                    SlotAccess::Read => &mut users[2],
+                };
+
+                if !users.contains(&idx) {
+                    users.push(idx);
+                }
+            }
+        }
+
+        let mut dependents: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+        let mut add_edge = |from: usize, to: usize| {
+            if from != to {
+                dependents.entry(from)
+                    .or_default()
+                    .insert(to);
+            }
+        };
+
+        for [writers, modifiers, readers] in slot_users.values() {
+            // every stage waits for the whole previous one,
+            // passes within a stage run one after another
+            let stages = [writers, modifiers, readers];
+            for (stage_idx, stage) in stages.iter().enumerate() {
+                if stage_idx < 2 {
+                    for pair in stage.windows(2) {
+                        add_edge(pair[0], pair[1]);
+                    }
+                }
+
+                let Some(previous) = stages[..stage_idx].iter().rev().find(|stage| !stage.is_empty()) else {
+                    continue;
+                };
+
+                for from in previous.iter() {
+                    for to in stage.iter() {
+                        add_edge(*from, *to);
+                    }
+                }
+            }
+        }
+
+        let mut in_degrees: HashMap<usize, usize> = active.iter()
+            .map(|idx| (*idx, 0))
+            .collect();
+
+        dependents.values()
+            .flatten()
+            .for_each(|idx| *in_degrees.get_mut(idx).unwrap() += 1);
+
+        // a set keeps the order stable between frames
+        let mut ready = in_degrees.iter()
+            .filter(|(_, in_degree)| **in_degree == 0)
+            .map(|(idx, _)| *idx)
+            .collect::<BTreeSet<_>>();
+
+        let mut order = Vec::with_capacity(active.len());
+        while let Some(idx) = ready.pop_first() {
+            order.push(idx);
+
+            for dependent in dependents.get(&idx).into_iter().flatten() {
+                let in_degree = in_degrees.get_mut(dependent).unwrap();
+                *in_degree -= 1;
+
+                if *in_degree == 0 {
+                    ready.insert(*dependent);
+                }
+            }
+        }
+
+        if order.len() != active.len() {
+            let names = active.iter()
+                .filter(|idx| !order.contains(idx))
+                .map(|idx| self.passes[*idx].name)
+                .collect();
+
+            return Err(RenderGraphError::Cycle(names));
+        }
+
+        Ok(order)
+    }
+
+    fn allocate_textures(&mut self, world: &mut World, order: &[usize]) {
+        let render_ctx = world.render_context();
+        let width = render_ctx.config.width;
+        let height = render_ctx.config.height;
+
+        let used_slots = order.iter()
+            .flat_map(|idx| self.passes[*idx].slots.iter())
+            .map(|(slot, _)| *slot)
+            .filter(|slot| self.transient_textures.contains_key(slot))
+            .collect::<BTreeSet<_>>();
+
+        // unused textures are dropped, resized ones recreated
+        self.allocated_textures.retain(|slot, allocated| {
+            used_slots.contains(slot)
+                && allocated.descriptor == self.transient_textures[slot]
+                && allocated.width == width
+                && allocated.height == height
+        });
+
+        for slot in used_slots.iter() {
+            if self.allocated_textures.contains_key(slot) {
+                continue;
+            }
+
+            let descriptor = self.transient_textures[slot];
+            let view = Self::create_texture(render_ctx, *slot, &descriptor);
+            self.allocated_textures.insert(*slot, AllocatedTexture {
+                descriptor,
+                width,
+                height,
+                view: Arc::new(view),
+            });
+        }
+
+        let mut frame_ctx = world.resource_mut::<FrameContext>();
+        for (slot, allocated) in self.allocated_textures.iter() {
+            frame_ctx.insert_view(*slot, allocated.view.clone());
+        }
+    }
+
+    fn create_texture(render_ctx: &RenderContext,
+        slot: SlotId,
+        descriptor: &TransientTexture,
+    ) -> wgpu::TextureView {
+        let texture = render_ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(slot.0),
+            size: wgpu::Extent3d {
+                width: render_ctx.config.width,
+                height: render_ctx.config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: descriptor.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: descriptor.format,
+            usage: descriptor.usage,
+            view_formats: &[],
+        });
+
+        texture.create_view(&wgpu::TextureViewDescriptor::default())
+    }
+}
@@ -1,8 +1,7 @@
-use std::sync::Arc;
-
 use bevy_ecs::system::Resource;
+use cgmath::Matrix4;
 
-use crate::{render::{material::Material, mesh::{AsMesh, Mesh}, multi_indexed_mesh::{AsMultiIndexedMesh, MultiIndexedMesh}}, AsModel, Texture};
+use crate::{asset::handle::Handle, render::{material::Material, mesh::{AsMesh, Mesh}, multi_indexed_mesh::{AsMultiIndexedMesh, MultiIndexedMesh}}, resources::asset_server::AssetServer, AsModel, InstanceRaw, Model, Texture};
 
 pub type MaterialId = usize;
 pub type ModelId = usize;
@@ -22,11 +21,13 @@ pub struct RenderServer {
 
 impl RenderServer {
     pub fn push_material(&mut self,
-        diffuse_texture: Arc<Texture>,
+        diffuse_texture: Handle<Texture>,
+        normal_texture: Handle<Texture>,
+        base_color: [f32; 4],
         device: &wgpu::Device,
     ) -> MaterialId {
         let material_id = self.free_material_id;
-        let material = Material::new(diffuse_texture, material_id, device);
+        let material = Material::new(diffuse_texture, normal_texture, base_color, material_id, device);
 
         self.materials.push(material);
         self.free_material_id += 1;
@@ -34,6 +35,35 @@ impl RenderServer {
         material_id
     }
 
+    // points materials at the latest version of their textures,
+    // returns how many materials were updated
+    pub fn refresh_materials(&mut self,
+        asset_server: &AssetServer,
+        device: &wgpu::Device,
+    ) -> usize {
+        let mut refreshed = 0;
+
+        let latest = |texture: Handle<Texture>| {
+            asset_server.get::<Texture>(texture.path())
+                .unwrap_or(texture)
+        };
+
+        for material in self.materials.iter_mut() {
+            let diffuse_texture = latest(material.diffuse_texture());
+            let normal_texture = latest(material.normal_texture());
+
+            let is_changed = !diffuse_texture.ptr_eq(&material.diffuse_texture())
+                || !normal_texture.ptr_eq(&material.normal_texture());
+
+            if is_changed {
+                material.set_textures(diffuse_texture, normal_texture, device);
+                refreshed += 1;
+            }
+        }
+
+        refreshed
+    }
+
     pub fn push_multi_indexed_mesh_ex(&mut self,
         as_multi_indexed_mesh: &impl AsMultiIndexedMesh,
         model_id_opt: Option<ModelId>,
@@ -89,6 +119,44 @@ impl RenderServer {
         mesh_id
     }
 
+    // pushes the model's materials and meshes, the meshes start
+    // without instances and are placed with ModelInstanceComponent
+    pub fn push_model(&mut self,
+        model: &Model,
+        device: &wgpu::Device,
+    ) -> ModelId {
+        let model_id = self.free_model_id;
+        let material_ids = model.materials()
+            .iter()
+            .map(|material| {
+                self.push_material(material.diffuse_texture.clone(),
+                    material.normal_texture.clone(),
+                    material.base_color,
+                    device,
+                )
+            }).collect::<Vec<_>>();
+
+        for model_mesh in model.meshes() {
+            let mesh_id = self.free_mesh_id;
+            let mut mesh = Mesh::new(&model_mesh.vertices,
+                &model_mesh.indices,
+                &[],
+                material_ids[model_mesh.material_idx],
+                mesh_id,
+                Some(model_id),
+                device
+            );
+
+            mesh.set_transform(model_mesh.transform);
+
+            self.meshes.push(mesh);
+            self.free_mesh_id += 1;
+        }
+
+        self.free_model_id += 1;
+        model_id
+    }
+
     pub fn push_multi_indexed_mesh(&mut self,
         as_multi_indexed_mesh: &impl AsMultiIndexedMesh,
         device: &wgpu::Device,
@@ -134,6 +202,28 @@ impl RenderServer {
         model_id
     }
 
+    pub fn write_mesh_instances(&mut self,
+        mesh_id: MeshId,
+        instances_raw: &[InstanceRaw],
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) {
+        let mesh_opt = self.meshes.iter_mut()
+            .find(|mesh| mesh_id == mesh.mesh_id());
+
+        match mesh_opt {
+            Some(mesh) => mesh.write_instances(instances_raw, device, queue),
+            None => log::warn!("Tried writing instances to unknown mesh {}", mesh_id),
+        }
+    }
+
+    pub fn model_meshes(&self, model_id: ModelId) -> Vec<(MeshId, Matrix4<f32>)> {
+        self.meshes.iter()
+            .filter(|mesh| *mesh.model_id() == Some(model_id))
+            .map(|mesh| (mesh.mesh_id(), mesh.transform()))
+            .collect()
+    }
+
     pub fn get_material(&self, material_id: MaterialId) -> &Material {
         self.materials.iter()
             .find(|material| material_id == material.material_id())
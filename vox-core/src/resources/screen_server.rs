@@ -1,9 +1,9 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Instant};
 
 use bevy_ecs::{schedule::{Schedule, SystemConfigs}, system::Resource, world::World};
-use crate::{screens::screen::Screen, world_ext::WorldExt};
+use crate::{render::render_graph::RenderGraphPass, screens::screen::Screen, world_ext::WorldExt};
 
-use super::game_state::GameState;
+use super::{frame_stats::FrameStats, game_state::GameState, render_graph::RenderGraph};
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
 enum Cycle {
@@ -11,6 +11,19 @@ enum Cycle {
     Ui,
     Draw,
     Update,
+    FixedUpdate,
+}
+
+impl Cycle {
+    fn name(&self) -> &'static str {
+        match self {
+            Cycle::Start => "Start",
+            Cycle::Ui => "Ui",
+            Cycle::Draw => "Draw",
+            Cycle::Update => "Update",
+            Cycle::FixedUpdate => "FixedUpdate",
+        }
+    }
 }
 
 #[derive(Resource, Default)]
@@ -18,11 +31,15 @@ pub struct ScreenServer {
     last_state: Option<GameState>,
     registered_screens: Vec<Box<dyn Screen>>,
     registered_schedules: HashMap<GameState, HashMap<Cycle, Schedule>>,
+    // screens are registered before the world exists,
+    // their passes reach the RenderGraph on the next draw
+    pending_passes: Vec<RenderGraphPass>,
 }
 
 impl ScreenServer {
     pub fn draw(&mut self, world: &mut World) {
         let state = world.game_state();
+        self.add_pending_passes(world);
 
         if self.should_run_start_systems(state) {
             self.set_last_state(state);
@@ -50,6 +67,19 @@ impl ScreenServer {
         self.run_schedule(world, state, Cycle::Update);
     }
 
+    pub fn fixed_update(&mut self, world: &mut World) {
+        let state = world.game_state();
+
+        if self.should_run_start_systems(state) {
+            self.set_last_state(state);
+            self.emit_event(world, Cycle::Start);
+            self.run_schedule(world, state, Cycle::Start);
+        }
+
+        self.emit_event(world, Cycle::FixedUpdate);
+        self.run_schedule(world, state, Cycle::FixedUpdate);
+    }
+
     pub fn register_screens(&mut self,
         vector: Vec<Box<dyn Screen>>
     ) {
@@ -76,6 +106,25 @@ impl ScreenServer {
         self.add_systems(state, Cycle::Ui, screen.ui_systems());
         self.add_systems(state, Cycle::Draw, screen.draw_systems());
         self.add_systems(state, Cycle::Update, screen.update_systems());
+        self.add_systems(state, Cycle::FixedUpdate, screen.fixed_update_systems());
+
+        if let Some(passes) = screen.render_passes() {
+            self.pending_passes.extend(passes.into_iter()
+                .map(|pass| pass.in_state(state)));
+        }
+    }
+
+    fn add_pending_passes(&mut self, world: &mut World) {
+        if self.pending_passes.is_empty() {
+            return;
+        }
+
+        let mut render_graph = world.resource_mut::<RenderGraph>();
+        for pass in self.pending_passes.drain(..) {
+            if let Err(e) = render_graph.add_pass(pass) {
+                log::error!("{}", e);
+            }
+        }
     }
 
     fn add_systems(&mut self,
@@ -122,6 +171,7 @@ impl ScreenServer {
                 match cycle {
                     Cycle::Start => screen.start(world),
                     Cycle::Update => screen.update(world),
+                    Cycle::FixedUpdate => screen.fixed_update(world),
                     Cycle::Ui => screen.ui(world),
                     Cycle::Draw => screen.draw(world),
                 }
@@ -135,7 +185,11 @@ impl ScreenServer {
     ) {
         if let Some(state_map) = self.get_state_map(state) {
             if let Some(schedule) = state_map.get_mut(&cycle) {
+                let start = Instant::now();
                 schedule.run(world);
+
+                world.resource_mut::<FrameStats>()
+                    .record_schedule(cycle.name(), start.elapsed());
             }
         }
     }
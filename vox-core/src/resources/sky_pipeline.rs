@@ -0,0 +1,180 @@
+use bevy_ecs::prelude::*;
+use bytemuck::Zeroable;
+use wgpu::{util::DeviceExt, PipelineCompilationOptions, RenderPipelineDescriptor};
+
+use crate::{render::{shader::{Shader, ShaderError}, sky::SkyUniform}, Texture};
+
+use super::default_pipeline::DefaultPipeline;
+
+// Draws the sky behind everything else, it runs inside the model
+// pass after the opaque geometry so only uncovered pixels are shaded
+#[derive(Resource)]
+pub struct SkyPipeline {
+    sky_buffer: wgpu::Buffer,
+    sky_bind_group: wgpu::BindGroup,
+    render_pipeline: wgpu::RenderPipeline,
+    render_pipeline_layout: wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    sample_count: u32,
+    // kept to rebuild the pipeline when the sample count changes
+    shader: Shader,
+}
+
+impl SkyPipeline {
+    pub const SHADER_FILE_NAME: &'static str = "sky.wgsl";
+
+    pub fn new(device: &wgpu::Device, sample_count: u32) -> Self {
+        let sky_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Sky Buffer"),
+            contents: bytemuck::bytes_of(&SkyUniform::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let sky_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sky Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let sky_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sky Bind Group"),
+            layout: &sky_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: sky_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sky Render Pipeline Layout"),
+            bind_group_layouts: &[
+                &sky_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let color_format = Texture::HDR_TEXTURE_FORMAT;
+        let shader = Shader::embedded(Self::SHADER_FILE_NAME, &[])
+            .unwrap();
+        let render_pipeline = DefaultPipeline::compile(device,
+            &shader,
+            |module| Self::create_render_pipeline(device, &render_pipeline_layout, module, color_format, sample_count),
+        ).unwrap();
+
+        Self {
+            sky_buffer,
+            sky_bind_group,
+            render_pipeline,
+            render_pipeline_layout,
+            color_format,
+            sample_count,
+            shader,
+        }
+    }
+
+    // the pipeline in use is only replaced by set_shader
+    pub fn compile_shader(&self,
+        device: &wgpu::Device,
+        load: impl Fn(&str, &[&str]) -> Result<Shader, ShaderError>,
+    ) -> anyhow::Result<(Shader, wgpu::RenderPipeline)> {
+        let shader = load(Self::SHADER_FILE_NAME, &[])?;
+        let render_pipeline = DefaultPipeline::compile(device,
+            &shader,
+            |module| Self::create_render_pipeline(device, &self.render_pipeline_layout, module, self.color_format, self.sample_count),
+        )?;
+
+        Ok((shader, render_pipeline))
+    }
+
+    pub fn set_shader(&mut self, (shader, render_pipeline): (Shader, wgpu::RenderPipeline)) {
+        self.render_pipeline = render_pipeline;
+        self.shader = shader;
+    }
+
+    // drawn inside the model pass, so it follows DefaultPipeline's
+    pub fn compile_sample_count(&self,
+        device: &wgpu::Device,
+        sample_count: u32,
+    ) -> anyhow::Result<wgpu::RenderPipeline> {
+        DefaultPipeline::compile(device,
+            &self.shader,
+            |module| Self::create_render_pipeline(device, &self.render_pipeline_layout, module, self.color_format, sample_count),
+        )
+    }
+
+    pub fn set_sample_count(&mut self,
+        render_pipeline: wgpu::RenderPipeline,
+        sample_count: u32,
+    ) {
+        self.render_pipeline = render_pipeline;
+        self.sample_count = sample_count;
+    }
+
+    fn create_render_pipeline(device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Sky Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // the sky sits on the far plane, so it only passes
+            // where nothing was drawn
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_TEXTURE_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    pub fn write_sky(&self, queue: &wgpu::Queue, sky_uniform: &SkyUniform) {
+        queue.write_buffer(&self.sky_buffer, 0, bytemuck::bytes_of(sky_uniform));
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.sky_bind_group, &[]);
+        // one triangle covering the screen
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,51 @@
+use bevy_ecs::system::Resource;
+
+// Frame and simulation timing, everything in seconds
+#[derive(Resource, Debug)]
+pub struct Time {
+    // between the last two frames
+    delta: f32,
+    // between two fixed updates, always the same
+    fixed_delta: f32,
+    elapsed: f32,
+    // how far the frame is past the last fixed update, from 0 to 1
+    alpha: f32,
+}
+
+impl Time {
+    pub fn new(fixed_delta: f32) -> Self {
+        Self {
+            delta: 0.0,
+            fixed_delta,
+            elapsed: 0.0,
+            alpha: 0.0,
+        }
+    }
+
+    // called once per frame, by App or HeadlessApp
+    pub fn advance(&mut self, delta: f32) {
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+
+    pub fn set_alpha(&mut self, alpha: f32) {
+        self.alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+
+    pub fn fixed_delta(&self) -> f32 {
+        self.fixed_delta
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    // for blending the last two fixed updates when drawing
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use bevy_ecs::system::Resource;
+
+use crate::{asset::{handle::Handle, AssetError}, render::{face_orientation::FaceOrientation, texture_array::TextureArrayBuilder}, voxel_registry::{VoxelRegistry, VoxelTypeIdentifier}, Texture};
+
+use super::asset_server::AssetServer;
+
+// Every voxel texture in one texture array,
+// chunk meshing looks up the layer of each face here
+#[derive(Resource, Debug)]
+pub struct VoxelAtlas {
+    texture: Handle<Texture>,
+    // kept to rebuild the texture when a layer's file changes
+    builder: TextureArrayBuilder,
+    // indexed by FaceOrientation::index
+    face_layers: HashMap<VoxelTypeIdentifier, [u32; 6]>,
+}
+
+impl VoxelAtlas {
+    pub const FILE_NAME: &'static str = "voxel_atlas";
+
+    pub fn build(voxel_registry: &VoxelRegistry,
+        asset_server: &mut AssetServer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Self, AssetError> {
+        let mut builder = TextureArrayBuilder::default();
+        let mut face_layers = HashMap::new();
+
+        for (voxel_id, textures) in voxel_registry.textured_types() {
+            let layers = textures.faces()
+                .each_ref()
+                .map(|file_name| builder.add(file_name));
+
+            face_layers.insert(voxel_id, layers);
+        }
+
+        // voxels without textures still need a layer to sample
+        if builder.is_empty() {
+            builder.add("debug.png");
+        }
+
+        let texture = builder.build(Self::FILE_NAME, asset_server, device, queue)?;
+        let texture = asset_server.insert(texture);
+
+        Ok(Self {
+            texture,
+            builder,
+            face_layers,
+        })
+    }
+
+    // the layers stay where they are, so meshed chunks keep
+    // their layers and only their material has to be refreshed
+    pub fn rebuild(&mut self,
+        asset_server: &mut AssetServer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<(), AssetError> {
+        let texture = self.builder.build(Self::FILE_NAME, asset_server, device, queue)?;
+        self.texture = asset_server.insert(texture);
+
+        Ok(())
+    }
+
+    pub fn contains_file(&self, file_name: &str) -> bool {
+        self.builder
+            .layer(file_name)
+            .is_some()
+    }
+
+    pub fn layer(&self, voxel_id: VoxelTypeIdentifier, orientation: FaceOrientation) -> u32 {
+        self.face_layers
+            .get(&voxel_id)
+            .map(|layers| layers[orientation.index() as usize])
+            .unwrap_or(0)
+    }
+
+    pub fn texture(&self) -> Handle<Texture> {
+        self.texture.clone()
+    }
+}
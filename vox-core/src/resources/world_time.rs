@@ -0,0 +1,80 @@
+use std::f32::consts::TAU;
+
+use bevy_ecs::system::Resource;
+use cgmath::{InnerSpace, Vector3};
+
+// The time of day drives the sun, the sky and the light
+#[derive(Resource, Debug)]
+pub struct WorldTime {
+    // 0 is midnight, 0.25 sunrise, 0.5 noon and 0.75 sunset
+    time_of_day: f32,
+    // in seconds
+    day_length: f32,
+    is_paused: bool,
+}
+
+impl Default for WorldTime {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_DAY_LENGTH)
+    }
+}
+
+impl WorldTime {
+    pub const DEFAULT_DAY_LENGTH: f32 = 600.0;
+    // the sun does not pass straight overhead
+    const SUN_TILT: f32 = 0.3;
+
+    pub fn new(day_length: f32) -> Self {
+        Self {
+            time_of_day: 0.3,
+            day_length,
+            is_paused: false,
+        }
+    }
+
+    // by the Time's delta, so headless runs and flythroughs are repeatable
+    pub fn advance(&mut self, seconds: f32) {
+        if self.is_paused || self.day_length <= 0.0 {
+            return;
+        }
+
+        self.time_of_day = (self.time_of_day + seconds / self.day_length)
+            .rem_euclid(1.0);
+    }
+
+    // points from the world towards the sun, the moon is opposite
+    pub fn sun_direction(&self) -> Vector3<f32> {
+        let angle = (self.time_of_day - 0.25) * TAU;
+        Vector3::new(angle.cos(), angle.sin(), Self::SUN_TILT)
+            .normalize()
+    }
+
+    // 1 at noon, 0 at sunrise and sunset, -1 at midnight
+    pub fn sun_elevation(&self) -> f32 {
+        self.sun_direction().y
+    }
+
+    pub fn set_time_of_day(&mut self, time_of_day: f32) {
+        self.time_of_day = time_of_day.rem_euclid(1.0);
+    }
+
+    pub fn time_of_day(&self) -> f32 {
+        self.time_of_day
+    }
+
+    pub fn set_day_length(&mut self, day_length: f32) {
+        self.day_length = day_length;
+    }
+
+    pub fn day_length(&self) -> f32 {
+        self.day_length
+    }
+
+    pub fn set_paused(&mut self, is_paused: bool) {
+        self.is_paused = is_paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+}
@@ -1,62 +1,87 @@
-use std::{sync::Arc, time::Instant};
 
-use bevy_ecs::{schedule::SystemConfigs, system::{Commands, Query, Res, ResMut}, world::World};
+use bevy_ecs::{schedule::{common_conditions::resource_exists, IntoSystemConfigs, SystemConfigs}, system::{Commands, Query, Res, ResMut}, world::World};
 use binary_greedy_meshing::CS_P;
-use cgmath::{InnerSpace, Matrix4, Quaternion, Rotation3, Zero};
-use egui_plot::PlotPoints;
+use cgmath::{InnerSpace, Matrix4, Point3, Quaternion, Rotation3, Zero};
 use wgpu::CommandEncoderDescriptor;
 
-use crate::{components::camerable::{CameraComponent, CameraUniform}, pass_ext::VoxDrawPassExt, render::{as_meshes::{chunk::Chunk, face::{FaceDescriptor}}, material::Material, mesh::AsMesh, multi_indexed_mesh::AsMultiIndexedMesh}, resources::{asset_server::AssetServer, default_pipeline::DefaultPipeline, egui_renderer::EguiRenderer, frame_context::FrameContext, game_state::GameState, glyphon_renderer::{LabelDescriptor, LabelId}, input::InputRes, mouse::MouseRes, render_context::RenderContext, render_server::RenderServer}, voxel_position::VoxelPosition, voxel_registry::VoxelType, world_ext::WorldExt, AsModel, InstanceData, Model, Texture};
+use crate::{components::{camerable::{CameraComponent, CameraUniform, OPENGL_TO_WGPU_MATRIX}, hierarchy::propagate_transforms, mesh_instance::sync_mesh_instances}, pass_ext::VoxDrawPassExt, render::{debug_view::DebugViewMode, render_graph::{RenderGraphPass, SlotId}, shadow_map::CASCADE_COUNT, sky::{SkyColors, SkyUniform}, as_meshes::{chunk::Chunk, face::{FaceDescriptor}}, material::Material, mesh::AsMesh, multi_indexed_mesh::AsMultiIndexedMesh}, resources::{asset_server::AssetServer, default_pipeline::DefaultPipeline, frame_context::FrameContext, flythrough::follow_flythrough, frame_stats::{frame_stats_panel, FrameStats}, game_state::GameState, glyphon_renderer::{GlyphonRenderer, LabelDescriptor, LabelId}, input::InputRes, mouse::MouseRes, render_context::RenderContext, render_server::RenderServer, fog::Fog, debug_pipeline::draw_debug_view, debug_view::{cycle_debug_view, DebugView}, gizmo_pipeline::draw_gizmos, gizmos::Gizmos, msaa::Msaa, post_process::post_process_panel, sky_pipeline::SkyPipeline, time::Time, voxel_atlas::VoxelAtlas, world_time::WorldTime}, voxel_position::VoxelPosition, voxel_registry::{VoxelRegistry, VoxelType}, AsModel, InstanceData, Model, Texture};
 
 use super::screen::Screen;
 
-#[rustfmt::skip]
-const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
-    1.0, 0.0, 0.0, 0.0,
-    0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 0.5, 0.5,
-    0.0, 0.0, 0.0, 1.0,
-);
+// owned by the DefaultPipeline, only orders the shadow and object passes
+const SHADOW_MAP: SlotId = SlotId("shadow_map");
 
 #[derive(Default)]
 pub struct GameScreen {
     label_id: Option<LabelId>,
-    elapsed: Option<Instant>,
     frame_counter: u16,
 }
 
 impl Screen for GameScreen {
+    // without a renderer (see HeadlessApp::new) there is no label
     fn start(&mut self, world: &mut World) {
-        let mut glyphon_renderer = world.glyphon_renderer_mut();
-
-        self.label_id = Some(glyphon_renderer.add_label(LabelDescriptor::default()));
-        self.elapsed = Some(Instant::now());
+        if let Some(mut glyphon_renderer) = world.get_resource_mut::<GlyphonRenderer>() {
+            self.label_id = Some(glyphon_renderer.add_label(LabelDescriptor::default()));
+        }
     }
 
     fn update(&mut self, world: &mut World) {
         self.frame_counter += 1;
 
-        let mut glyphon_renderer = world.glyphon_renderer_mut();
-
         if self.frame_counter >= 80 {
-            let fps = 1000 / (1 + self.elapsed.unwrap().elapsed().as_millis());
-            let string = format!("FPS: {:?}", fps);
-            glyphon_renderer.set_text(self.label_id.unwrap(), string);
+            let fps = world.resource::<FrameStats>().fps();
+            let string = format!("FPS: {:.0}", fps);
+            let glyphon_renderer = world.get_resource_mut::<GlyphonRenderer>();
+            if let (Some(label_id), Some(mut glyphon_renderer)) = (self.label_id, glyphon_renderer) {
+                glyphon_renderer.set_text(label_id, string);
+            }
             self.frame_counter = 0;
         }
-        self.elapsed = Some(Instant::now());
     }
 
     fn start_systems(&self) -> Option<SystemConfigs> {
-        self.to_systems((spawn_camera, spawn_chunks))
+        self.to_systems((spawn_camera, spawn_chunks).run_if(resource_exists::<RenderContext>))
     }
 
-    fn update_systems(&self) -> Option<SystemConfigs> {
+    fn ui_systems(&self) -> Option<SystemConfigs> {
+        self.to_systems((post_process_panel, frame_stats_panel))
+    }
+
+    fn fixed_update_systems(&self) -> Option<SystemConfigs> {
         self.to_systems(update_camera)
     }
 
+    fn update_systems(&self) -> Option<SystemConfigs> {
+        self.to_systems((follow_flythrough, update_world_time, cycle_debug_view))
+    }
+
     fn draw_systems(&self) -> Option<SystemConfigs> {
-        self.to_systems((draw_objects, draw_camera))
+        self.to_systems((
+            (propagate_transforms, sync_mesh_instances).chain(),
+            draw_camera,
+            write_world_light,
+            draw_chunk_borders,
+        ))
+    }
+
+    fn render_passes(&self) -> Option<Vec<RenderGraphPass>> {
+        Some(vec![
+            RenderGraphPass::new("shadows", draw_shadows)
+                .writes(SHADOW_MAP),
+            RenderGraphPass::new("objects", draw_objects)
+                .reads(SHADOW_MAP)
+                .writes(SlotId::HDR_MULTISAMPLED)
+                .writes(SlotId::HDR)
+                .writes(SlotId::DEPTH),
+            RenderGraphPass::new("debug_view", draw_debug_view)
+                .modifies(SlotId::HDR_MULTISAMPLED)
+                .modifies(SlotId::HDR)
+                .reads(SlotId::DEPTH),
+            RenderGraphPass::new("gizmos", draw_gizmos)
+                .modifies(SlotId::HDR_MULTISAMPLED)
+                .modifies(SlotId::HDR)
+                .reads(SlotId::DEPTH),
+        ])
     }
 
     fn game_state(&self) -> GameState {
@@ -64,20 +89,20 @@ impl Screen for GameScreen {
     }
 }
 
-pub fn draw_stats(egui_renderer: Res<EguiRenderer>) {
-
-}
-
+// a fixed update system, draw_camera blends between its steps
 pub fn update_camera(mut query: Query<&mut CameraComponent>,
     input_res: Res<InputRes>,
     mouse_res: Res<MouseRes>,
+    time: Res<Time>,
 ) {
     for mut camera_cmpnt in &mut query {
+        camera_cmpnt.store_previous();
+
         let forward = camera_cmpnt.target - camera_cmpnt.position;
         let forward_norm = forward.normalize();
         let forward_mag = forward.magnitude();
 
-        let speed = camera_cmpnt.speed;
+        let speed = camera_cmpnt.speed * time.fixed_delta();
 
         if input_res.forward.is_pressed && forward_mag > speed {
             camera_cmpnt.position += forward_norm * speed;
@@ -108,6 +133,28 @@ pub fn update_camera(mut query: Query<&mut CameraComponent>,
     }
 }
 
+// moves the sun, write_world_light uploads what it lights
+pub fn update_world_time(mut world_time: ResMut<WorldTime>,
+    time: Res<Time>,
+) {
+    world_time.advance(time.delta());
+}
+
+// feeds the sun's light and the fog to the default pipeline
+pub fn write_world_light(world_time: Res<WorldTime>,
+    mut pipeline: ResMut<DefaultPipeline>,
+    fog: Res<Fog>,
+    render_ctx: Res<RenderContext>,
+) {
+    let sun_direction = world_time.sun_direction();
+    let sky_colors = SkyColors::from_sun_elevation(sun_direction.y);
+    let shininess = pipeline.light().shininess;
+
+    pipeline.set_light(&render_ctx.queue, sky_colors.light(sun_direction, shininess));
+    pipeline.set_clear_color(sky_colors.clear_color());
+    pipeline.write_fog(&render_ctx.queue, &fog.uniform(sky_colors.horizon.into()));
+}
+
 pub fn spawn_chunks(mut asset_server: ResMut<AssetServer>,
     mut render_server: ResMut<RenderServer>,
     mut commands: Commands,
@@ -124,16 +171,20 @@ pub fn spawn_chunks(mut asset_server: ResMut<AssetServer>,
         }
     }
 
-    chunk.update_faces();
-
     let device = &render_ctx.device;
     let queue = &render_ctx.queue;
 
-    let texture = Texture::debug(&mut asset_server, device, queue);
-    let material_id = render_server.push_material(texture, device);
-    let texture2 = asset_server.get_or_load::<Texture>("dirt.png", device, queue)
+    let voxel_atlas = VoxelAtlas::build(&VoxelRegistry::default(),
+        &mut asset_server,
+        device,
+        queue,
+    ).unwrap();
+
+    let normal_texture = Texture::flat_normal(&mut asset_server, device, queue)
         .unwrap();
-    let material_id2 = render_server.push_material(texture2, device);
+    let material_id = render_server.push_material(voxel_atlas.texture(), normal_texture, Material::WHITE, device);
+    chunk.set_material_id(material_id);
+    chunk.update_faces(&voxel_atlas);
     //let materials = vec![material];
     //
     //let chunk_model = chunk.to_model(materials);
@@ -163,6 +214,7 @@ pub fn spawn_chunks(mut asset_server: ResMut<AssetServer>,
     //render_server.push_mesh(&face2, device);
     // render_server.push_multi_indexed_mesh(&chunk, device);
     render_server.push_multi_indexed_mesh(&chunk, device);
+    commands.insert_resource(voxel_atlas);
 }
 
 pub fn spawn_camera(mut commands: Commands,
@@ -171,19 +223,53 @@ pub fn spawn_camera(mut commands: Commands,
     commands.spawn(CameraComponent::debug(&render_ctx.config));
 }
 
+pub fn draw_shadows(render_ctx: Res<RenderContext>,
+    mut frame_ctx: ResMut<FrameContext>,
+    pipeline: Res<DefaultPipeline>,
+    render_server: Res<RenderServer>,
+) {
+    let mut encoder = render_ctx.device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("Shadow Encoder"),
+    });
+
+    for cascade in 0..CASCADE_COUNT {
+        let mut render_pass = pipeline.shadow_pass(&mut encoder, cascade);
+
+        for mesh in render_server.meshes() {
+            render_pass.draw_mesh_geometry(mesh);
+        }
+
+        for multi_indexed_mesh in render_server.multi_indexed_meshes() {
+            render_pass.draw_mesh_multi_indexed_geometry(multi_indexed_mesh);
+        }
+    }
+
+    frame_ctx.add_encoder(encoder);
+}
+
 pub fn draw_objects(render_ctx: Res<RenderContext>,
     mut frame_ctx: ResMut<FrameContext>,
     pipeline: Res<DefaultPipeline>,
+    sky_pipeline: Res<SkyPipeline>,
     render_server: Res<RenderServer>,
+    msaa: Res<Msaa>,
 ) {
-    let view = &frame_ctx.view;
+    let view = frame_ctx.view(SlotId::HDR);
     let mut encoder = render_ctx.device.create_command_encoder(&CommandEncoderDescriptor {
         label: Some("Object Encoder"),
     });
 
+   // with MSAA the samples are resolved into the HDR texture
+   let (view, resolve_target) = if msaa.applied_sample_count() > 1 {
+       (frame_ctx.view(SlotId::HDR_MULTISAMPLED), Some(view))
+   } else {
+       (view, None)
+   };
+
    let mut render_pass = pipeline
        .model_pass(&mut encoder, view,
-           render_ctx.depth_texture.view()
+           resolve_target,
+           frame_ctx.view(SlotId::DEPTH)
        );
 
    for mesh in render_server.meshes() {
@@ -196,6 +282,8 @@ pub fn draw_objects(render_ctx: Res<RenderContext>,
         );
    }
 
+   // chunks sample the voxel atlas, a texture array
+   render_pass.set_pipeline(pipeline.voxel_render_pipeline());
    for multi_indexed_mesh in render_server.multi_indexed_meshes() {
        let material_id = multi_indexed_mesh.material_id();
        let material = render_server.get_material(material_id);
@@ -206,15 +294,39 @@ pub fn draw_objects(render_ctx: Res<RenderContext>,
         );
    }
 
+   // last, so it only shades what the geometry left uncovered
+   sky_pipeline.draw(&mut render_pass);
+
     frame_ctx.add_encoder(encoder);
 }
 
+// outlines the chunk while a debug view is on
+pub fn draw_chunk_borders(debug_view: Res<DebugView>,
+    mut gizmos: ResMut<Gizmos>,
+) {
+    if debug_view.mode == DebugViewMode::Off {
+        return;
+    }
+
+    let size = CS_P as f32;
+    gizmos.aabb(Point3::new(0.0, 0.0, 0.0), Point3::new(size, size, size), Gizmos::YELLOW);
+
+    gizmos.set_depth_test(false);
+    gizmos.axes(Point3::new(0.0, 0.0, 0.0), 4.0);
+    gizmos.set_depth_test(true);
+}
+
 // TODO: move this engine side
 pub fn draw_camera(query: Query<&CameraComponent>,
     render_ctx: Res<RenderContext>,
     pipeline: Res<DefaultPipeline>,
+    sky_pipeline: Res<SkyPipeline>,
+    world_time: Res<WorldTime>,
+    time: Res<Time>,
 ) {
     for camera_cmpnt in &query {
+        let camera_cmpnt = &camera_cmpnt.interpolated(time.alpha());
+
         let view = Matrix4::look_at_rh(
             camera_cmpnt.position,
             camera_cmpnt.target,
@@ -228,10 +340,20 @@ pub fn draw_camera(query: Query<&CameraComponent>,
             camera_cmpnt.zfar
         );
         
-        let uniform: CameraUniform = (OPENGL_TO_WGPU_MATRIX * proj * view)
-            .into();
+        let view_proj = OPENGL_TO_WGPU_MATRIX * proj * view;
+        let uniform = CameraUniform::new(view_proj,
+            camera_cmpnt.position
+        );
         
         render_ctx.queue.write_buffer(pipeline.camera_buffer(),
-            0, bytemuck::cast_slice(&uniform));
+            0, bytemuck::bytes_of(&uniform));
+
+        pipeline.update_shadows(&render_ctx.queue, camera_cmpnt);
+
+        let sun_direction = world_time.sun_direction();
+        let sky_colors = SkyColors::from_sun_elevation(sun_direction.y);
+        let sky_uniform = SkyUniform::new(view_proj, sun_direction, &sky_colors);
+
+        sky_pipeline.write_sky(&render_ctx.queue, &sky_uniform);
     }
 }
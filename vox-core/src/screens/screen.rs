@@ -1,6 +1,6 @@
 use bevy_ecs::{schedule::{IntoSystemConfigs, SystemConfigs}, world::World};
 
-use crate::resources::game_state::GameState;
+use crate::{render::render_graph::RenderGraphPass, resources::game_state::GameState};
 
 #[allow(unused_variables)]
 pub trait Screen
@@ -11,11 +11,17 @@ where Self: Send + Sync + 'static {
     fn ui(&mut self, world: &mut World) {}
     fn draw(&mut self, world: &mut World) {}
     fn update(&mut self, world: &mut World) {}
+    // runs every Time::fixed_delta, zero or more times per frame
+    fn fixed_update(&mut self, world: &mut World) {}
 
     fn start_systems(&self) -> Option<SystemConfigs> { None }
     fn ui_systems(&self) -> Option<SystemConfigs> { None }
     fn draw_systems(&self) -> Option<SystemConfigs> { None }
     fn update_systems(&self) -> Option<SystemConfigs> { None }
+    fn fixed_update_systems(&self) -> Option<SystemConfigs> { None }
+
+    // only run while the screen's game state is active
+    fn render_passes(&self) -> Option<Vec<RenderGraphPass>> { None }
 
     fn to_systems<M>(&self,
         systems: impl IntoSystemConfigs<M>, 
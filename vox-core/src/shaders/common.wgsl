@@ -0,0 +1,190 @@
+#include "instance.wgsl"
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    view_position: vec4<f32>,
+}
+
+struct LightUniform {
+    // the direction the light travels in
+    direction: vec3<f32>,
+    shininess: f32,
+    color: vec3<f32>,
+    ambient: vec3<f32>,
+}
+
+struct MaterialUniform {
+    // multiplied with the diffuse texture
+    base_color: vec4<f32>,
+}
+
+// must match FogMode::index
+const FOG_DISABLED: u32 = 0u;
+const FOG_LINEAR: u32 = 1u;
+const FOG_EXPONENTIAL: u32 = 2u;
+const FOG_EXPONENTIAL_SQUARED: u32 = 3u;
+
+struct FogUniform {
+    color: vec3<f32>,
+    mode: u32,
+    start: f32,
+    end: f32,
+    density: f32,
+}
+
+// must match CASCADE_COUNT and ShadowMap::SIZE
+const CASCADE_COUNT: u32 = 3u;
+const SHADOW_MAP_SIZE: f32 = 2048.0;
+
+struct ShadowUniform {
+    cascades: array<mat4x4<f32>, CASCADE_COUNT>,
+    // where every cascade ends along camera_forward
+    splits: vec4<f32>,
+    camera_forward: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) @interpolate(flat) texture_layer: u32,
+    @location(2) world_position: vec3<f32>,
+    @location(3) world_normal: vec3<f32>,
+    @location(4) world_tangent: vec4<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> camera: CameraUniform;
+
+@group(1) @binding(1)
+var<uniform> light: LightUniform;
+
+@group(1) @binding(2)
+var<uniform> fog: FogUniform;
+
+@group(2) @binding(0)
+var<uniform> shadow: ShadowUniform;
+
+@group(2) @binding(1)
+var t_shadow: texture_depth_2d_array;
+
+@group(2) @binding(2)
+var s_shadow: sampler_comparison;
+
+// TEXTURE_ARRAY is defined for the voxel pipeline
+#ifdef TEXTURE_ARRAY
+@group(0) @binding(0)
+var t_diffuse: texture_2d_array<f32>;
+#else
+@group(0) @binding(0)
+var t_diffuse: texture_2d<f32>;
+#endif
+
+@group(0) @binding(1)
+var s_diffuse: sampler;
+
+@group(0) @binding(2)
+var t_normal: texture_2d<f32>;
+
+@group(0) @binding(3)
+var s_normal: sampler;
+
+@group(0) @binding(4)
+var<uniform> material: MaterialUniform;
+
+fn sample_diffuse(tex_coords: vec2<f32>, texture_layer: u32) -> vec4<f32> {
+#ifdef TEXTURE_ARRAY
+    let diffuse = textureSample(t_diffuse, s_diffuse, tex_coords, texture_layer);
+#else
+    let diffuse = textureSample(t_diffuse, s_diffuse, tex_coords);
+#endif
+    return diffuse * material.base_color;
+}
+
+// the normal map is in tangent space, this moves it to world space
+fn sample_normal(in: VertexOutput) -> vec3<f32> {
+    let normal = normalize(in.world_normal);
+    let tangent = normalize(in.world_tangent.xyz - normal * dot(normal, in.world_tangent.xyz));
+    let bitangent = cross(normal, tangent) * in.world_tangent.w;
+    let tbn = mat3x3<f32>(tangent, bitangent, normal);
+
+    let tangent_normal = textureSample(t_normal, s_normal, in.tex_coords).xyz * 2.0 - 1.0;
+    return normalize(tbn * tangent_normal);
+}
+
+// 1 when fully lit, 0 when fully in shadow
+fn shadow_factor(world_position: vec3<f32>, geometry_normal: vec3<f32>) -> f32 {
+    let depth = dot(world_position - camera.view_position.xyz, shadow.camera_forward.xyz);
+
+    var cascade = CASCADE_COUNT;
+    for (var i = 0u; i < CASCADE_COUNT; i++) {
+        if depth < shadow.splits[i] {
+            cascade = i;
+            break;
+        }
+    }
+
+    if cascade == CASCADE_COUNT {
+        return 1.0;
+    }
+
+    // further cascades have bigger texels and need a bigger offset against acne
+    let normal_offset = geometry_normal * 0.02 * f32(cascade + 1u);
+    let light_position = shadow.cascades[cascade] * vec4<f32>(world_position + normal_offset, 1.0);
+    let ndc = light_position.xyz / light_position.w;
+    let uv = ndc.xy * vec2<f32>(0.5, -0.5) + 0.5;
+
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || ndc.z > 1.0 {
+        return 1.0;
+    }
+
+    // 3x3 PCF, every tap is also filtered by the comparison sampler
+    let texel = 1.0 / SHADOW_MAP_SIZE;
+    var lit = 0.0;
+    for (var x = -1; x <= 1; x++) {
+        for (var y = -1; y <= 1; y++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(t_shadow, s_shadow, uv + offset, cascade, ndc.z);
+        }
+    }
+
+    return lit / 9.0;
+}
+
+// Blinn-Phong with the directional light, lit is the shadow_factor
+// and only hides the direct light
+fn shade(albedo: vec3<f32>,
+    normal: vec3<f32>,
+    world_position: vec3<f32>,
+    lit: f32,
+) -> vec3<f32> {
+    let light_dir = -normalize(light.direction);
+    let view_dir = normalize(camera.view_position.xyz - world_position);
+    let half_dir = normalize(view_dir + light_dir);
+
+    let diffuse = max(dot(normal, light_dir), 0.0);
+    let specular = pow(max(dot(normal, half_dir), 0.0), light.shininess) * step(0.0, dot(normal, light_dir));
+
+    return (light.ambient + (diffuse + specular) * lit * light.color) * albedo;
+}
+
+// blends towards the fog color with the distance to the camera
+fn apply_fog(color: vec3<f32>, world_position: vec3<f32>) -> vec3<f32> {
+    let distance = length(world_position - camera.view_position.xyz);
+
+    var amount = 0.0;
+    switch fog.mode {
+        case FOG_LINEAR: {
+            amount = (distance - fog.start) / max(fog.end - fog.start, 0.0001);
+        }
+        case FOG_EXPONENTIAL: {
+            amount = 1.0 - exp(-fog.density * distance);
+        }
+        case FOG_EXPONENTIAL_SQUARED: {
+            let density_distance = fog.density * distance;
+            amount = 1.0 - exp(-density_distance * density_distance);
+        }
+        default: {}
+    }
+
+    return mix(color, fog.color, clamp(amount, 0.0, 1.0));
+}
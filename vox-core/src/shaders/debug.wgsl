@@ -0,0 +1,84 @@
+#include "instance.wgsl"
+
+// must match CameraUniform in common.wgsl
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    view_position: vec4<f32>,
+}
+
+// must match DebugViewMode::index
+const DEBUG_WIREFRAME: u32 = 1u;
+const DEBUG_FACE_ORIENTATION: u32 = 2u;
+const DEBUG_CHUNK_TINT: u32 = 3u;
+const DEBUG_QUAD_OUTLINES: u32 = 4u;
+
+struct DebugUniform {
+    color: vec4<f32>,
+    mode: u32,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) world_normal: vec3<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+@group(1) @binding(0)
+var<uniform> debug: DebugUniform;
+
+@vertex
+fn vs_main(
+    model: VertexInput, instance: InstanceInput
+) -> VertexOutput {
+    let model_matrix = instance_matrix(instance);
+    let normal_matrix = instance_normal_matrix(instance);
+
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.world_normal = normal_matrix * model.normal;
+    out.clip_position = camera.view_proj * model_matrix * vec4<f32>(model.position, 1.0);
+    return out;
+}
+
+// keeps the faces of a tinted shape apart
+fn facing_shade(normal: vec3<f32>) -> f32 {
+    return 0.6 + 0.4 * abs(dot(normal, normalize(vec3<f32>(0.3, 1.0, 0.5))));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.world_normal);
+    // before any branch, derivatives need every fragment
+    let texel_width = fwidth(in.tex_coords) * 1.5;
+
+    switch debug.mode {
+        case DEBUG_FACE_ORIENTATION: {
+            let is_positive = dot(normal, vec3<f32>(1.0)) > 0.0;
+            return vec4<f32>(abs(normal) * select(0.4, 1.0, is_positive), 1.0);
+        }
+        case DEBUG_CHUNK_TINT: {
+            return vec4<f32>(debug.color.rgb * facing_shade(normal), 1.0);
+        }
+        case DEBUG_QUAD_OUTLINES: {
+            // tex_coords go from 0 to 1 across every quad
+            let edge_distance = min(in.tex_coords, 1.0 - in.tex_coords);
+            if all(edge_distance > texel_width) {
+                discard;
+            }
+
+            return debug.color;
+        }
+        default: {
+            return debug.color;
+        }
+    }
+}
@@ -0,0 +1,28 @@
+struct InstanceInput {
+    @location(5) model_mat_0: vec4<f32>,
+    @location(6) model_mat_1: vec4<f32>,
+    @location(7) model_mat_2: vec4<f32>,
+    @location(8) model_mat_3: vec4<f32>,
+    @location(9) texture_layer: u32,
+    @location(10) normal_mat_0: vec3<f32>,
+    @location(11) normal_mat_1: vec3<f32>,
+    @location(12) normal_mat_2: vec3<f32>,
+}
+
+fn instance_matrix(instance: InstanceInput) -> mat4x4<f32> {
+    return mat4x4<f32>(
+        instance.model_mat_0,
+        instance.model_mat_1,
+        instance.model_mat_2,
+        instance.model_mat_3,
+    );
+}
+
+// for normals and tangents, see InstanceRaw::new
+fn instance_normal_matrix(instance: InstanceInput) -> mat3x3<f32> {
+    return mat3x3<f32>(
+        instance.normal_mat_0,
+        instance.normal_mat_1,
+        instance.normal_mat_2,
+    );
+}
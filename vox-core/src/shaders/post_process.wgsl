@@ -0,0 +1,144 @@
+// Every effect is a fullscreen pass reading the previous one's output,
+// see PostEffect::entry_point
+
+struct PostProcessUniform {
+    exposure: f32,
+    gamma: f32,
+    lut_strength: f32,
+    lut_size: f32,
+    texel_size: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var t_input: texture_2d<f32>;
+@group(0) @binding(1)
+var s_input: sampler;
+
+@group(0) @binding(2)
+var<uniform> post: PostProcessUniform;
+
+// sRGB encoded, so it is looked up with sRGB coordinates
+@group(0) @binding(3)
+var t_lut: texture_3d<f32>;
+@group(0) @binding(4)
+var s_lut: sampler;
+
+const FXAA_SPAN_MAX: f32 = 8.0;
+const FXAA_REDUCE_MUL: f32 = 0.125;
+const FXAA_REDUCE_MIN: f32 = 0.0078125;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // (0, 0), (2, 0), (0, 2)
+    let uv = vec2<f32>(
+        f32((vertex_index << 1u) & 2u),
+        f32(vertex_index & 2u),
+    );
+
+    var out: VertexOutput;
+    out.uv = uv;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    return out;
+}
+
+fn sample_input(uv: vec2<f32>) -> vec3<f32> {
+    return textureSample(t_input, s_input, uv).rgb;
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// perceptual brightness, edges are found on this
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(sqrt(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0))), vec3<f32>(0.299, 0.587, 0.114));
+}
+
+@fragment
+fn fs_blit(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(sample_input(in.uv), 1.0);
+}
+
+@fragment
+fn fs_exposure(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(sample_input(in.uv) * exp2(post.exposure), 1.0);
+}
+
+// Narkowicz's fit of the ACES filmic curve
+@fragment
+fn fs_tonemapping(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = max(sample_input(in.uv), vec3<f32>(0.0));
+    let mapped = (color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14);
+
+    return vec4<f32>(clamp(mapped, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0);
+}
+
+@fragment
+fn fs_color_grading(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = clamp(sample_input(in.uv), vec3<f32>(0.0), vec3<f32>(1.0));
+
+    // keeps the lookup between the first and last texel centers
+    let scale = (post.lut_size - 1.0) / post.lut_size;
+    let offset = 0.5 / post.lut_size;
+    let graded = textureSample(t_lut, s_lut, linear_to_srgb(color) * scale + offset).rgb;
+
+    return vec4<f32>(mix(color, graded, post.lut_strength), 1.0);
+}
+
+@fragment
+fn fs_fxaa(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = post.texel_size;
+
+    let rgb_nw = sample_input(in.uv + vec2<f32>(-1.0, -1.0) * texel);
+    let rgb_ne = sample_input(in.uv + vec2<f32>(1.0, -1.0) * texel);
+    let rgb_sw = sample_input(in.uv + vec2<f32>(-1.0, 1.0) * texel);
+    let rgb_se = sample_input(in.uv + vec2<f32>(1.0, 1.0) * texel);
+    let rgb_m = sample_input(in.uv);
+
+    let luma_nw = luma(rgb_nw);
+    let luma_ne = luma(rgb_ne);
+    let luma_sw = luma(rgb_sw);
+    let luma_se = luma(rgb_se);
+    let luma_m = luma(rgb_m);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // blurs along the edge, not across it
+    var direction = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+
+    let direction_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+    let inverse_direction_min = 1.0 / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+    direction = clamp(direction * inverse_direction_min, vec2<f32>(-FXAA_SPAN_MAX), vec2<f32>(FXAA_SPAN_MAX)) * texel;
+
+    let rgb_a = 0.5 * (
+        sample_input(in.uv + direction * (1.0 / 3.0 - 0.5))
+        + sample_input(in.uv + direction * (2.0 / 3.0 - 0.5))
+    );
+    let rgb_b = rgb_a * 0.5 + 0.25 * (
+        sample_input(in.uv + direction * -0.5)
+        + sample_input(in.uv + direction * 0.5)
+    );
+
+    // the wider blur went past the edge
+    let luma_b = luma(rgb_b);
+    let is_outside = luma_b < luma_min || luma_b > luma_max;
+
+    return vec4<f32>(select(rgb_b, rgb_a, is_outside), 1.0);
+}
+
+@fragment
+fn fs_gamma(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = max(sample_input(in.uv), vec3<f32>(0.0));
+    return vec4<f32>(pow(color, vec3<f32>(1.0 / post.gamma)), 1.0);
+}
@@ -0,0 +1,38 @@
+#include "common.wgsl"
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) tangent: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput, instance: InstanceInput
+) -> VertexOutput {
+    let model_matrix = instance_matrix(instance);
+    let normal_matrix = instance_normal_matrix(instance);
+
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
+
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.texture_layer = instance.texture_layer;
+    out.world_position = world_position.xyz;
+    out.world_normal = normal_matrix * model.normal;
+    out.world_tangent = vec4<f32>(normal_matrix * model.tangent.xyz, model.tangent.w);
+    out.clip_position = camera.view_proj * world_position;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let albedo = sample_diffuse(in.tex_coords, in.texture_layer);
+    let normal = sample_normal(in);
+    let lit = shadow_factor(in.world_position, normalize(in.world_normal));
+
+    let color = shade(albedo.rgb, normal, in.world_position, lit);
+
+    return vec4<f32>(apply_fog(color, in.world_position), albedo.a);
+}
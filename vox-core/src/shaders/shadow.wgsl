@@ -0,0 +1,21 @@
+#include "instance.wgsl"
+
+// the sun's view of one cascade
+struct CascadeUniform {
+    view_proj: mat4x4<f32>,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> cascade: CascadeUniform;
+
+@vertex
+fn vs_main(
+    model: VertexInput, instance: InstanceInput
+) -> @builtin(position) vec4<f32> {
+    let model_matrix = instance_matrix(instance);
+    return cascade.view_proj * model_matrix * vec4<f32>(model.position, 1.0);
+}
@@ -0,0 +1,59 @@
+struct SkyUniform {
+    inverse_view_proj: mat4x4<f32>,
+    // points towards the sun, the moon is opposite
+    sun_direction: vec4<f32>,
+    zenith_color: vec4<f32>,
+    horizon_color: vec4<f32>,
+    sun_color: vec4<f32>,
+    moon_color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> sky: SkyUniform;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // (-1, -1), (3, -1), (-1, 3)
+    let ndc = vec2<f32>(
+        f32((vertex_index << 1u) & 2u) * 2.0 - 1.0,
+        f32(vertex_index & 2u) * 2.0 - 1.0,
+    );
+
+    var out: VertexOutput;
+    out.ndc = ndc;
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    return out;
+}
+
+fn view_direction(ndc: vec2<f32>) -> vec3<f32> {
+    let near = sky.inverse_view_proj * vec4<f32>(ndc, 0.0, 1.0);
+    let far = sky.inverse_view_proj * vec4<f32>(ndc, 1.0, 1.0);
+    return normalize(far.xyz / far.w - near.xyz / near.w);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let direction = view_direction(in.ndc);
+
+    // below the horizon fades to a darker horizon color
+    let height = clamp(direction.y, 0.0, 1.0);
+    let below = clamp(-direction.y * 4.0, 0.0, 1.0);
+    var color = mix(sky.horizon_color.rgb, sky.zenith_color.rgb, sqrt(height));
+    color = mix(color, sky.horizon_color.rgb * 0.5, below);
+
+    let sun_amount = dot(direction, sky.sun_direction.xyz);
+    let sun_disk = smoothstep(0.9990, 0.9995, sun_amount);
+    let sun_glow = pow(max(sun_amount, 0.0), 64.0) * 0.5;
+    color += sky.sun_color.rgb * (sun_disk * 10.0 + sun_glow);
+
+    // the moon is drawn brighter than the light it casts
+    let moon_disk = smoothstep(0.9994, 0.9997, -sun_amount);
+    color += sky.moon_color.rgb * moon_disk * 6.0;
+
+    return vec4<f32>(color, 1.0);
+}
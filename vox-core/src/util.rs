@@ -1,14 +1,20 @@
 use std::{ffi::OsStr, path::Path};
 
-// FIXME: This function will not work with WASM!
-// https://sotrh.github.io/learn-wgpu/beginner/tutorial9-models/#accessing-files-from-wasm
-pub fn load_binary(file_name: &str) -> anyhow::Result<Vec<u8>> {
-    let path = std::path::Path::new(env!("VOX_OUTPUT_DIR"))
-        .join("res")
-        .join(file_name);
+// resolves "." and ".." so every path to a file maps to the same key
+pub fn normalize_path(file_name: &str) -> String {
+    let mut components: Vec<&str> = Vec::new();
 
-    let data = std::fs::read(path)?;
-    Ok(data)
+    for component in file_name.split(['/', '\\']) {
+        match component {
+            "" | "." => {},
+            ".." => {
+                components.pop();
+            },
+            _ => components.push(component),
+        }
+    }
+
+    components.join("/")
 }
 
 pub fn get_extension(file_name: &str) -> Option<&str> {
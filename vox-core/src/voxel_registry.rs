@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use log::debug;
 
+use crate::render::face_orientation::FaceOrientation;
+
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Debug)]
 pub enum VoxelType {
     AIR,
@@ -10,21 +12,61 @@ pub enum VoxelType {
 
 pub type VoxelTypeIdentifier = u16;
 
+// the texture used by each face, indexed by FaceOrientation::index
+#[derive(Debug, Clone)]
+pub struct VoxelTextures {
+    faces: [String; 6],
+}
+
+impl VoxelTextures {
+    pub fn all(file_name: &str) -> Self {
+        let faces = std::array::from_fn(|_| file_name.to_string());
+
+        Self {
+            faces,
+        }
+    }
+
+    pub fn top_bottom_sides(top: &str, bottom: &str, sides: &str) -> Self {
+        Self::all(sides)
+            .with_face(FaceOrientation::UP, top)
+            .with_face(FaceOrientation::DOWN, bottom)
+    }
+
+    pub fn with_face(mut self, orientation: FaceOrientation, file_name: &str) -> Self {
+        self.faces[orientation.index() as usize] = file_name.to_string();
+        self
+    }
+
+    pub fn face(&self, orientation: FaceOrientation) -> &str {
+        &self.faces[orientation.index() as usize]
+    }
+
+    pub fn faces(&self) -> &[String; 6] {
+        &self.faces
+    }
+}
+
 #[derive(Debug)]
 pub struct VoxelRegistry {
     type_registry: HashMap<VoxelType, VoxelTypeIdentifier>,
+    texture_registry: HashMap<VoxelType, VoxelTextures>,
 }
 
 impl Default for VoxelRegistry {
     fn default() -> Self {
         let type_registry = HashMap::new();
+        let texture_registry = HashMap::new();
         let mut registry = Self {
             type_registry,
+            texture_registry,
         };
 
         registry.register_type(VoxelType::AIR, 0);
         registry.register_type(VoxelType::DIRT, 1);
 
+        registry.register_textures(VoxelType::DIRT, VoxelTextures::all("dirt.png"));
+
         registry
     }
 }
@@ -40,6 +82,31 @@ impl VoxelRegistry {
         }
     }
 
+    pub fn register_textures(&mut self,
+        voxel_type: VoxelType,
+        textures: VoxelTextures,
+    ) {
+        let opt = self.texture_registry.insert(voxel_type, textures);
+        if opt.is_some() {
+            debug!("Replaced textures for voxel type {:?}", voxel_type);
+        }
+    }
+
+    pub fn get_textures(&self, voxel_type: VoxelType) -> Option<&VoxelTextures> {
+        self.texture_registry
+            .get(&voxel_type)
+    }
+
+    // every voxel type that has textures, with its id
+    pub fn textured_types(&self) -> impl Iterator<Item = (VoxelTypeIdentifier, &VoxelTextures)> {
+        self.texture_registry
+            .iter()
+            .filter_map(|(voxel_type, textures)| {
+                self.get_id(*voxel_type)
+                    .map(|id| (id, textures))
+            })
+    }
+
     pub fn get_id(&self, voxel_type: VoxelType) -> Option<VoxelTypeIdentifier> {
         self.type_registry
             .get(&voxel_type)
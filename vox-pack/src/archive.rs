@@ -0,0 +1,178 @@
+use std::{collections::HashMap, io::{self, Read}, path::Path};
+
+use flate2::read::DeflateDecoder;
+
+use crate::{hash, invalid_data, Compression, HASH_LEN, HEADER_LEN, MAGIC, VERSION};
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub offset: usize,
+    pub stored_len: usize,
+    pub len: usize,
+    pub compression: Compression,
+    pub hash: [u8; HASH_LEN],
+}
+
+// The whole archive is kept in memory, entries are
+// decompressed and checked against their hash when read
+pub struct Archive {
+    data: Vec<u8>,
+    entries: HashMap<String, Entry>,
+}
+
+impl Archive {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let data = std::fs::read(path)?;
+        Self::from_bytes(data)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> io::Result<Self> {
+        let mut reader = Reader {
+            data: &data,
+            position: 0,
+        };
+
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(invalid_data("not an asset archive"));
+        }
+
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(invalid_data(format!("unsupported archive version {}", version)));
+        }
+
+        let entry_count = reader.u32()?;
+        let index_hash = reader.hash()?;
+
+        let mut entries = HashMap::new();
+        for _ in 0..entry_count {
+            let name_len = reader.u16()? as usize;
+            let name = std::str::from_utf8(reader.bytes(name_len)?)
+                .map_err(invalid_data)?
+                .to_string();
+
+            let offset = reader.u64()? as usize;
+            let stored_len = reader.u64()? as usize;
+            let len = reader.u64()? as usize;
+            let compression = Compression::from_byte(reader.u8()?)
+                .ok_or_else(|| invalid_data(format!("{} has an unknown compression", name)))?;
+            let hash = reader.hash()?;
+
+            let is_in_bounds = offset.checked_add(stored_len)
+                .is_some_and(|end| end <= data.len());
+
+            if !is_in_bounds {
+                return Err(invalid_data(format!("{} is out of bounds", name)));
+            }
+
+            let entry = Entry {
+                offset,
+                stored_len,
+                len,
+                compression,
+                hash,
+            };
+
+            entries.insert(name, entry);
+        }
+
+        if hash(&data[HEADER_LEN..reader.position]) != index_hash {
+            return Err(invalid_data("archive index is corrupted"));
+        }
+
+        Ok(Self {
+            data,
+            entries,
+        })
+    }
+
+    pub fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let entry = self.entries.get(name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} not found in archive", name)))?;
+
+        let stored = &self.data[entry.offset..entry.offset + entry.stored_len];
+        let bytes = match entry.compression {
+            Compression::None => stored.to_vec(),
+            Compression::Deflate => {
+                let mut bytes = Vec::with_capacity(entry.len);
+                DeflateDecoder::new(stored)
+                    .read_to_end(&mut bytes)?;
+
+                bytes
+            },
+        };
+
+        if bytes.len() != entry.len || hash(&bytes) != entry.hash {
+            return Err(invalid_data(format!("{} is corrupted", name)));
+        }
+
+        Ok(bytes)
+    }
+
+    // reads every entry, slow but catches any corruption
+    pub fn verify(&self) -> io::Result<()> {
+        for name in self.entries.keys() {
+            self.read(name)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&Entry> {
+        self.entries.get(name)
+    }
+
+    pub fn file_names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys()
+            .map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position + len)
+            .ok_or_else(|| invalid_data("unexpected end of archive"))?;
+
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn hash(&mut self) -> io::Result<[u8; HASH_LEN]> {
+        self.array()
+    }
+}
@@ -0,0 +1,138 @@
+use std::{io::{self, Write}, path::Path};
+
+use flate2::write::DeflateEncoder;
+
+use crate::{hash, invalid_data, Compression, HASH_LEN, HEADER_LEN, MAGIC, VERSION};
+
+struct PendingEntry {
+    name: String,
+    stored: Vec<u8>,
+    len: usize,
+    compression: Compression,
+    hash: [u8; HASH_LEN],
+}
+
+#[derive(Default)]
+pub struct ArchiveBuilder {
+    entries: Vec<PendingEntry>,
+}
+
+impl ArchiveBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // deflate is only kept when it makes the entry smaller
+    pub fn add(&mut self, name: &str, data: &[u8], compression: Compression) -> io::Result<()> {
+        if name.len() > u16::MAX as usize {
+            return Err(invalid_data(format!("{} is too long", name)));
+        }
+
+        let (stored, compression) = match compression {
+            Compression::None => (data.to_vec(), Compression::None),
+            Compression::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(data)?;
+                let compressed = encoder.finish()?;
+
+                if compressed.len() < data.len() {
+                    (compressed, Compression::Deflate)
+                } else {
+                    (data.to_vec(), Compression::None)
+                }
+            },
+        };
+
+        // adding the same name twice replaces the old entry
+        self.entries.retain(|entry| entry.name != name);
+        self.entries.push(PendingEntry {
+            name: name.to_string(),
+            stored,
+            len: data.len(),
+            compression,
+            hash: hash(data),
+        });
+
+        Ok(())
+    }
+
+    // adds every file under dir with its path relative to dir,
+    // hidden files are skipped
+    pub fn add_dir(&mut self, dir: &Path, compression: Compression) -> io::Result<usize> {
+        let mut files = Vec::new();
+        collect_files(dir, &mut files)?;
+        files.sort();
+
+        for file in files.iter() {
+            let name = file.strip_prefix(dir)
+                .map_err(invalid_data)?
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            let data = std::fs::read(file)?;
+            self.add(&name, &data, compression)?;
+        }
+
+        Ok(files.len())
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, usize, usize, Compression)> {
+        self.entries.iter()
+            .map(|entry| (entry.name.as_str(), entry.len, entry.stored.len(), entry.compression))
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        let index_len = self.entries.iter()
+            .map(|entry| 2 + entry.name.len() + 8 + 8 + 8 + 1 + HASH_LEN)
+            .sum::<usize>();
+
+        let mut offset = HEADER_LEN + index_len;
+        let mut index = Vec::with_capacity(index_len);
+        for entry in self.entries.iter() {
+            index.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            index.extend_from_slice(entry.name.as_bytes());
+            index.extend_from_slice(&(offset as u64).to_le_bytes());
+            index.extend_from_slice(&(entry.stored.len() as u64).to_le_bytes());
+            index.extend_from_slice(&(entry.len as u64).to_le_bytes());
+            index.push(entry.compression.to_byte());
+            index.extend_from_slice(&entry.hash);
+
+            offset += entry.stored.len();
+        }
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        writer.write_all(&hash(&index))?;
+        writer.write_all(&index)?;
+
+        for entry in self.entries.iter() {
+            writer.write_all(&entry.stored)?;
+        }
+
+        writer.flush()
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.write(io::BufWriter::new(file))
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<std::path::PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_hidden = path.file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+        if is_hidden {
+            continue;
+        } else if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
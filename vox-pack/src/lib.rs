@@ -0,0 +1,49 @@
+pub mod archive;
+pub mod builder;
+
+pub use archive::Archive;
+pub use builder::ArchiveBuilder;
+
+// Layout, little endian:
+// header: magic, version: u32, entry_count: u32, index_hash: [u8; 32]
+// per entry: name_len: u16, name, offset: u64, stored_len: u64, len: u64,
+//     compression: u8, hash: [u8; 32]
+// then the entry data, offsets are from the start of the file
+//
+// the index hash covers every entry after the header,
+// entry hashes cover the uncompressed data
+pub const MAGIC: &[u8; 8] = b"VOXPACK\0";
+pub const VERSION: u32 = 2;
+pub const HASH_LEN: usize = 32;
+pub const HEADER_LEN: usize = MAGIC.len() + 4 + 4 + HASH_LEN;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Deflate,
+}
+
+impl Compression {
+    fn to_byte(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Deflate => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Deflate),
+            _ => None,
+        }
+    }
+}
+
+pub fn hash(data: &[u8]) -> [u8; HASH_LEN] {
+    *blake3::hash(data).as_bytes()
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
@@ -0,0 +1,271 @@
+use std::{collections::BTreeMap, hint::black_box, path::{Path, PathBuf}, time::{Duration, Instant}};
+
+use binary_greedy_meshing::CS;
+use serde::{Deserialize, Serialize};
+use vox_core::{device_ext::VoxDeviceExt, render::{as_meshes::chunk::Chunk, multi_indexed_mesh::AsMultiIndexedMesh}, resources::{asset_server::AssetServer, voxel_atlas::VoxelAtlas}, voxel_position::VoxelPosition, voxel_registry::{VoxelRegistry, VoxelType}};
+use xtask_wasm::{anyhow::{anyhow, Context, Result}, clap};
+
+#[derive(clap::Parser)]
+pub struct Bench {
+    /// Where to write the results, relative to the workspace
+    #[arg(long, default_value = "target/bench/results.json")]
+    output: PathBuf,
+    /// Results of an earlier run to compare against
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    /// Slowdown in percent that counts as a regression
+    #[arg(long, default_value_t = 10.0)]
+    threshold: f64,
+    /// Timed runs of every workload, after one warm up run
+    #[arg(long, default_value_t = 50)]
+    iterations: u32,
+    /// Only run the workloads whose name contains this
+    #[arg(long)]
+    filter: Option<String>,
+}
+
+// Chunk contents the workloads mesh, all of them deterministic
+#[derive(Clone, Copy)]
+enum Pattern {
+    Solid,
+    Sphere,
+    // the worst case for greedy meshing, no two faces merge
+    Checkerboard,
+    Terrain,
+}
+
+impl Pattern {
+    const ALL: [Pattern; 4] = [
+        Pattern::Solid,
+        Pattern::Sphere,
+        Pattern::Checkerboard,
+        Pattern::Terrain,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Pattern::Solid => "solid",
+            Pattern::Sphere => "sphere",
+            Pattern::Checkerboard => "checkerboard",
+            Pattern::Terrain => "terrain",
+        }
+    }
+
+    fn is_filled(&self, x: usize, y: usize, z: usize) -> bool {
+        match self {
+            Pattern::Solid => true,
+            Pattern::Sphere => {
+                let center = CS as f32 / 2.0;
+                let distance = |a: usize| (a as f32 - center).powi(2);
+                (distance(x) + distance(y) + distance(z)).sqrt() < center
+            },
+            Pattern::Checkerboard => (x + y + z).is_multiple_of(2),
+            Pattern::Terrain => {
+                let x = x as f32 * 0.15;
+                let z = z as f32 * 0.2;
+                let height = 24.0 + 8.0 * x.sin() + 6.0 * z.cos() + 4.0 * (x + z).sin();
+                (y as f32) < height
+            },
+        }
+    }
+
+    fn chunk(&self) -> Chunk {
+        let mut chunk = Chunk::default();
+        for x in 0..CS {
+            for y in 0..CS {
+                for z in 0..CS {
+                    if self.is_filled(x, y, z) {
+                        chunk.set_voxel_type_at(VoxelPosition::from((x, y, z)), VoxelType::DIRT);
+                    }
+                }
+            }
+        }
+
+        chunk
+    }
+}
+
+// Timings of one workload in nanoseconds
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct BenchResult {
+    iterations: u32,
+    median: u64,
+    mean: u64,
+    min: u64,
+    max: u64,
+}
+
+impl BenchResult {
+    fn new(mut samples: Vec<Duration>) -> Self {
+        samples.sort();
+
+        let nanos = |duration: &Duration| duration.as_nanos() as u64;
+        let total = samples.iter().map(nanos).sum::<u64>();
+
+        Self {
+            iterations: samples.len() as u32,
+            median: nanos(&samples[samples.len() / 2]),
+            mean: total / samples.len() as u64,
+            min: nanos(&samples[0]),
+            max: nanos(&samples[samples.len() - 1]),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct BenchReport {
+    adapter: String,
+    results: BTreeMap<String, BenchResult>,
+}
+
+impl Bench {
+    pub fn run(self) -> Result<()> {
+        let workspace_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .context("xtask is not in a workspace")?;
+
+        let (adapter, device, queue) = pollster::block_on(request_device())?;
+        let mut asset_server = AssetServer::default();
+        let voxel_atlas = VoxelAtlas::build(&VoxelRegistry::default(),
+            &mut asset_server,
+            &device,
+            &queue,
+        )?;
+
+        let mut report = BenchReport {
+            adapter: adapter.get_info().name,
+            ..Default::default()
+        };
+
+        for pattern in Pattern::ALL {
+            let mut chunk = pattern.chunk();
+            chunk.update_faces(&voxel_atlas);
+
+            self.measure(&mut report, pattern, "update_faces", || {
+                chunk.update_faces(&voxel_atlas);
+            });
+
+            self.measure(&mut report, pattern, "instances", || {
+                black_box(chunk.instances());
+            });
+
+            self.measure(&mut report, pattern, "indirect_indexed_args", || {
+                black_box(chunk.indirect_indexed_args());
+            });
+
+            let instances = chunk.instances();
+            let indirect_indexed_args = chunk.indirect_indexed_args();
+            // waits for the gpu, otherwise only the copy into staging is timed
+            self.measure(&mut report, pattern, "upload", || {
+                black_box(device.compute_instance_buffer(&instances));
+                black_box(device.compute_indirect_indexed_buffer(&indirect_indexed_args));
+                queue.submit([]);
+                device.poll(wgpu::Maintain::Wait);
+            });
+        }
+
+        let output = workspace_dir.join(&self.output);
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&output, serde_json::to_string_pretty(&report)?)
+            .with_context(|| format!("Could not write {}", output.display()))?;
+
+        log::error!("Wrote {} results to {}", report.results.len(), output.display());
+
+        match &self.baseline {
+            Some(baseline) => self.compare(&report, &workspace_dir.join(baseline)),
+            None => Ok(()),
+        }
+    }
+
+    fn measure(&self,
+        report: &mut BenchReport,
+        pattern: Pattern,
+        workload: &str,
+        mut func: impl FnMut(),
+    ) {
+        let name = format!("{}/{}", workload, pattern.name());
+        if let Some(filter) = &self.filter {
+            if !name.contains(filter.as_str()) {
+                return;
+            }
+        }
+
+        func();
+
+        let samples = (0..self.iterations.max(1))
+            .map(|_| {
+                let start = Instant::now();
+                func();
+                start.elapsed()
+            })
+            .collect();
+
+        let result = BenchResult::new(samples);
+        log::error!("{:<36} median {:>12?}  min {:>12?}",
+            name,
+            Duration::from_nanos(result.median),
+            Duration::from_nanos(result.min),
+        );
+
+        report.results.insert(name, result);
+    }
+
+    // medians are compared, they move the least between runs
+    fn compare(&self, report: &BenchReport, baseline: &Path) -> Result<()> {
+        let baseline = std::fs::read_to_string(baseline)
+            .with_context(|| format!("Could not read {}", baseline.display()))?;
+        let baseline: BenchReport = serde_json::from_str(&baseline)?;
+
+        if baseline.adapter != report.adapter {
+            log::error!("The baseline ran on {}, not {}", baseline.adapter, report.adapter);
+        }
+
+        let mut regressions = Vec::new();
+        for (name, result) in &report.results {
+            let Some(baseline_result) = baseline.results.get(name) else {
+                log::error!("{:<36} not in the baseline", name);
+                continue;
+            };
+
+            let change = (result.median as f64 / baseline_result.median.max(1) as f64 - 1.0) * 100.0;
+            log::error!("{:<36} {:>+8.1}%", name, change);
+
+            if change > self.threshold {
+                regressions.push(name.as_str());
+            }
+        }
+
+        if regressions.is_empty() {
+            return Ok(());
+        }
+
+        Err(anyhow!("{} regressed by more than {}%: {}",
+            regressions.len(),
+            self.threshold,
+            regressions.join(", "),
+        ))
+    }
+}
+
+// no surface, so it also runs on machines without a display
+async fn request_device() -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+    let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        compatible_surface: None,
+        force_fallback_adapter: false,
+    }).await.context("No graphics adapter found")?;
+
+    let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+        label: Some("Bench Device"),
+        required_features: wgpu::Features::empty(),
+        required_limits: wgpu::Limits::default(),
+        memory_hints: wgpu::MemoryHints::default(),
+    }, None).await?;
+
+    Ok((adapter, device, queue))
+}
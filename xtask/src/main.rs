@@ -1,4 +1,9 @@
+mod pack;
+mod bench;
+
 use std::process::Command;
+use pack::Pack;
+use bench::Bench;
 use xtask_wasm::{anyhow::Result, clap, default_dist_dir};
 
 #[derive(clap::Parser)]
@@ -6,6 +11,8 @@ enum Opt {
     Dist(xtask_wasm::Dist),
     Watch(xtask_wasm::Watch),
     Start(xtask_wasm::DevServer),
+    Pack(Pack),
+    Bench(Bench),
 }
 
 
@@ -38,6 +45,16 @@ fn main() -> Result<()> {
 
             dev_server.arg("dist").start(default_dist_dir(false))?;
         }
+        Opt::Pack(pack) => {
+            log::error!("Packing assets...");
+
+            pack.run()?;
+        }
+        Opt::Bench(bench) => {
+            log::error!("Running benchmarks...");
+
+            bench.run()?;
+        }
     }
 
     Ok(())
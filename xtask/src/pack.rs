@@ -0,0 +1,64 @@
+use std::path::{Path, PathBuf};
+
+use vox_pack::{Archive, ArchiveBuilder, Compression};
+use xtask_wasm::{anyhow::{Context, Result}, clap};
+
+#[derive(clap::Parser)]
+pub struct Pack {
+    /// Folder to pack, relative to the workspace
+    #[arg(long, default_value = "vox-core/res")]
+    input: PathBuf,
+    /// Archive to write, the engine looks for res.pack next to its executable
+    #[arg(long, default_value = "target/release/res.pack")]
+    output: PathBuf,
+    /// Store every file without compressing it
+    #[arg(long)]
+    no_compression: bool,
+}
+
+impl Pack {
+    pub fn run(self) -> Result<()> {
+        let workspace_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .context("xtask is not in a workspace")?;
+
+        let input = workspace_dir.join(&self.input);
+        let output = workspace_dir.join(&self.output);
+
+        let compression = if self.no_compression {
+            Compression::None
+        } else {
+            Compression::Deflate
+        };
+
+        let mut builder = ArchiveBuilder::new();
+        let file_count = builder.add_dir(&input, compression)
+            .with_context(|| format!("Could not read {}", input.display()))?;
+
+        let (len, stored_len) = builder.entries()
+            .fold((0, 0), |(len, stored_len), (_, entry_len, entry_stored_len, _)| {
+                (len + entry_len, stored_len + entry_stored_len)
+            });
+
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        builder.write_to_file(&output)
+            .with_context(|| format!("Could not write {}", output.display()))?;
+
+        // read everything back so a broken archive never ships
+        Archive::open(&output)
+            .and_then(|archive| archive.verify())
+            .with_context(|| format!("{} failed the integrity check", output.display()))?;
+
+        log::error!("Packed {} files into {} ({} -> {} bytes)",
+            file_count,
+            output.display(),
+            len,
+            stored_len,
+        );
+
+        Ok(())
+    }
+}